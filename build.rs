@@ -12,7 +12,19 @@ fn main() {
         .qt_module("OpenGL")
         .qml_module(QmlModule {
             uri: "com.kdab.servo",
-            rust_files: &["src/webview.rs"],
+            rust_files: &[
+                "src/authenticationrequest.rs",
+                "src/beforeunloadrequest.rs",
+                "src/fileselectionrequest.rs",
+                "src/fullscreenrequest.rs",
+                "src/javascriptdialog.rs",
+                "src/mediasession.rs",
+                "src/permissionrequest.rs",
+                "src/profile.rs",
+                "src/settings.rs",
+                "src/webresourcerequest.rs",
+                "src/webview.rs",
+            ],
             qml_files: &["qml/main.qml", "qml/ServoToolbar.qml"],
             qrc_files: &[
                 "images/arrow-back.png",
@@ -27,6 +39,7 @@ fn main() {
             println!("cargo:rerun-if-changed=cpp/helpers.cpp");
         })
         .file("src/renderer.rs")
+        .file("src/urlscheme.rs")
         .qobject_header("cpp/helpers.h")
         .with_opts(cxx_qt_lib_headers::build_opts())
         .build();
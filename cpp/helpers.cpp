@@ -7,6 +7,10 @@
 
 #include "helpers.h"
 
+#include <QFile>
+#include <QJsonArray>
+#include <QJsonDocument>
+#include <QMimeDatabase>
 #include <QOpenGLFramebufferObject>
 #include <QOpenGLContext>
 #include <QOpenGLFunctions>
@@ -19,6 +23,17 @@ blitFramebuffer(QOpenGLFramebufferObject* target, ::std::unique_ptr<QOpenGLFrame
     QOpenGLFramebufferObject::blitFramebuffer(target, source.get());
 }
 
+void
+clearFramebuffer(QOpenGLFramebufferObject* target)
+{
+    Q_ASSERT(target != nullptr);
+    Q_ASSERT(target->isBound());
+
+    QOpenGLFunctions *f = QOpenGLContext::currentContext()->functions();
+    f->glClearColor(1.0f, 1.0f, 1.0f, 1.0f);
+    f->glClear(GL_COLOR_BUFFER_BIT);
+}
+
 ::std::unique_ptr<QOpenGLFramebufferObject>
 fboFromTexture(unsigned int texture_id, unsigned int texture_target, QSize size)
 {
@@ -30,6 +45,59 @@ fboFromTexture(unsigned int texture_id, unsigned int texture_target, QSize size)
     return fbo;
 }
 
+QVariant
+qvariantFromJson(::rust::Str json)
+{
+    const auto document = QJsonDocument::fromJson(
+      QByteArrayLiteral("[") + QByteArray(json.data(), static_cast<qsizetype>(json.size())) +
+      QByteArrayLiteral("]"));
+    return document.array().at(0).toVariant();
+}
+
+::rust::String
+qvariantToJson(QVariant const& value)
+{
+    // Values from QML such as objects arrive as a QJSValue
+    auto variant = value;
+    if (variant.userType() == qMetaTypeId<QJSValue>()) {
+        variant = variant.value<QJSValue>().toVariant();
+    }
+
+    const auto array = QJsonArray{ QJsonValue::fromVariant(variant) };
+    const auto json = QJsonDocument(array).toJson(QJsonDocument::Compact);
+    // Remove the brackets of the array
+    const auto inner = json.mid(1, json.size() - 2);
+    return ::rust::String(inner.constData(), static_cast<::std::size_t>(inner.size()));
+}
+
+void
+qjsvalueCallWithError(QJSValue& callback, QString const& error)
+{
+    if (!callback.isCallable()) {
+        return;
+    }
+
+    callback.call({ QJSValue(QJSValue::UndefinedValue), QJSValue(error) });
+}
+
+bool
+qresourceRead(QString const& path, QByteArray& data)
+{
+    QFile file(path);
+    if (!file.open(QIODevice::ReadOnly)) {
+        return false;
+    }
+
+    data = file.readAll();
+    return true;
+}
+
+QString
+qmimeTypeForFileName(QString const& path)
+{
+    return QMimeDatabase().mimeTypeForFile(path, QMimeDatabase::MatchExtension).name();
+}
+
 ::rust::isize
 qTouchEventPointCount(QTouchEvent const& event)
 {
@@ -8,11 +8,23 @@
 #pragma once
 
 #include <memory>
+#include <string>
 
+#include <QtCore/QByteArray>
+#include <QtCore/QMetaMethod>
+#include <QtCore/QMetaObject>
+#include <QtCore/QRect>
 #include <QtCore/QSize>
+#include <QtCore/QString>
+#include <QtCore/QStringList>
+#include <QtCore/QVariant>
 #include <QtGui/QEventPoint>
+#include <QtGui/QScreen>
 #include <QtGui/QTouchEvent>
+#include <QtQml/QJSEngine>
+#include <QtQml/QJSValue>
 #include <QtQuick/QQuickFramebufferObject>
+#include <QtQuick/QQuickWindow>
 
 #include "rust/cxx.h"
 
@@ -28,6 +40,76 @@ template<typename T, typename... Args>
     return ::std::make_unique<T>(args...);
 }
 
+// Used to find out if QML has a handler for a signal
+template<typename T>
+bool
+qobjectIsSignalConnected(T const& object, ::rust::Str signature)
+{
+    const auto normalized = QMetaObject::normalizedSignature(::std::string(signature).c_str());
+    const auto index = object.metaObject()->indexOfSignal(normalized.constData());
+    return index >= 0 && object.isSignalConnected(object.metaObject()->method(index));
+}
+
+// Geometry of the screen and window which an item is shown in
+template<typename T>
+QRect
+qquickItemScreenGeometry(T const& item)
+{
+    const auto window = item.window();
+    return window && window->screen() ? window->screen()->geometry() : QRect();
+}
+
+template<typename T>
+QRect
+qquickItemScreenAvailableGeometry(T const& item)
+{
+    const auto window = item.window();
+    return window && window->screen() ? window->screen()->availableGeometry() : QRect();
+}
+
+template<typename T>
+QRect
+qquickItemWindowGeometry(T const& item)
+{
+    const auto window = item.window();
+    return window ? window->frameGeometry() : QRect();
+}
+
+// Convert between JSON and QVariant, a JSON document cannot be a plain value
+// so the value is wrapped in an array
+QVariant
+qvariantFromJson(::rust::Str json);
+
+::rust::String
+qvariantToJson(QVariant const& value);
+
+// Call a QML callback with a value which is given as JSON
+template<typename T>
+void
+qjsvalueCallWithJson(T const& context, QJSValue& callback, ::rust::Str json)
+{
+    const auto engine = qjsEngine(&context);
+    if (!engine || !callback.isCallable()) {
+        return;
+    }
+
+    callback.call({ engine->toScriptValue(qvariantFromJson(json)) });
+}
+
+// Call a QML callback with an undefined value and an error message
+void
+qjsvalueCallWithError(QJSValue& callback, QString const& error);
+
+// Read a file from Qt resources, used to serve custom URL schemes
+bool
+qresourceRead(QString const& path, QByteArray& data);
+
+QString
+qmimeTypeForFileName(QString const& path);
+
+void
+clearFramebuffer(QOpenGLFramebufferObject* target);
+
 void
 blitFramebuffer(QOpenGLFramebufferObject* target, ::std::unique_ptr<QOpenGLFramebufferObject> source);
 
@@ -14,15 +14,36 @@ ColumnLayout {
 
     signal goBack()
     signal goForward()
+    signal reload()
+    signal reloadBypassingCache()
+    signal stop()
     signal urlRequest(url requestedUrl)
 
     property alias canGoBack: backButton.enabled
     property alias canGoForward: forwardButton.enabled
     property url faviconUrl
+    property bool loading
     property url webViewUrl
 
     spacing: 0
 
+    Shortcut {
+        sequences: [StandardKey.Refresh, "Ctrl+R"]
+        onActivated: root.reload()
+    }
+
+    Shortcut {
+        sequences: ["Shift+F5", "Ctrl+Shift+R"]
+        onActivated: root.reloadBypassingCache()
+    }
+
+    // Escape leaves fullscreen while the toolbar is hidden
+    Shortcut {
+        enabled: root.loading && root.visible
+        sequence: StandardKey.Cancel
+        onActivated: root.stop()
+    }
+
     // Toolbar of controls for Servo
     RowLayout {
         Layout.preferredHeight: 64
@@ -5,6 +5,7 @@
 
 import QtQuick 2.12
 import QtQuick.Controls 2.12
+import QtQuick.Dialogs
 import QtQuick.Layouts 1.12
 import QtQuick.Window 2.12
 
@@ -18,20 +19,38 @@ Window {
     height: 800
     width: 1280
 
+    // Whether the window is waiting for the page to close
+    property bool closeRequested: false
+    property bool pageClosed: false
+
+    // Let the page confirm before the window closes and Servo quits
+    onClosing: (close) => {
+        if (!pageClosed) {
+            close.accepted = false;
+            closeRequested = true;
+            webView.tryClose();
+        }
+    }
+
     ColumnLayout {
         anchors.fill: parent
         spacing: 0
 
         ServoToolbar {
             id: toolbar
+            visible: !webView.isFullScreen
             canGoBack: webView.canGoBack
             canGoForward: webView.canGoForward
             faviconUrl: webView.faviconUrl
+            loading: webView.loading
             webViewUrl: webView.url
             Layout.fillWidth: true
 
             onGoBack: webView.goBack()
             onGoForward: webView.goForward()
+            onReload: webView.reload()
+            onReloadBypassingCache: webView.reloadBypassingCache()
+            onStop: webView.stop()
             onUrlRequest: (requestedUrl) => webView.url = requestedUrl
         }
 
@@ -41,7 +60,132 @@ Window {
             Layout.fillHeight: true
             Layout.fillWidth: true
             url: "https://servo.org/"
+
+            onAuthenticationRequired: (request) => authenticationDialog.openRequest(request)
+            onFullScreenRequested: (request) => request.accept()
+            onIsFullScreenChanged: {
+                root.visibility = webView.isFullScreen ? Window.FullScreen : Window.AutomaticVisibility;
+            }
+            onGeometryChangeRequested: (geometry) => {
+                // Only honour requests from the page when we are not fullscreen
+                if (!webView.isFullScreen) {
+                    root.x = geometry.x;
+                    root.y = geometry.y;
+                    root.width = geometry.width;
+                    root.height = geometry.height;
+                }
+            }
+            onFileSelectionRequested: (request) => fileDialog.openRequest(request)
+            onJavaScriptDialogRequested: (dialog) => javaScriptDialog.openRequest(dialog)
+
+            onPermissionRequested: (request) => permissionDialog.openRequest(request)
+
+            onBeforeUnloadRequested: (request) => beforeUnloadDialog.openRequest(request)
+            onWindowCloseRequested: {
+                if (root.closeRequested) {
+                    root.pageClosed = true;
+                    root.close();
+                }
+            }
+
+            onRenderProcessTerminated: (reason, backtrace) => {
+                console.warn("Servo crashed:", reason, backtrace);
+                crashedBar.visible = true;
+            }
+
+            onLoadRequestChanged: (loadRequest) => {
+                if (loadRequest.status === ServoWebView.LoadFailedStatus) {
+                    console.warn("Failed to load", loadRequest.url, loadRequest.errorString);
+                }
+            }
+        }
+    }
+
+    // Shown over the last frame after a crash
+    Pane {
+        id: crashedBar
+        anchors.horizontalCenter: parent.horizontalCenter
+        anchors.top: parent.top
+        anchors.topMargin: toolbar.visible ? toolbar.height + 12 : 12
+        visible: false
+
+        RowLayout {
+            Label {
+                text: qsTr("The page has crashed")
+            }
+
+            Button {
+                text: qsTr("Restart")
+                onClicked: {
+                    crashedBar.visible = false;
+                    webView.restart();
+                }
+            }
+        }
+    }
+
+    // Status bubble showing the link under the pointer
+    Label {
+        anchors.bottom: parent.bottom
+        anchors.bottomMargin: 12
+        anchors.left: parent.left
+        background: Rectangle {
+            border.color: Qt.rgba(0, 0, 0, 0.3)
+            color: "white"
         }
+        elide: Text.ElideMiddle
+        padding: 4
+        text: webView.statusText
+        visible: text.length > 0
+        width: Math.min(implicitWidth, parent.width / 2)
+    }
+
+    // Media controls for the media session of the page
+    Pane {
+        anchors.bottom: parent.bottom
+        anchors.bottomMargin: 12
+        anchors.right: parent.right
+        anchors.rightMargin: 12
+        visible: webView.mediaSession.playbackState !== ServoMediaSession.NoSession
+
+        RowLayout {
+            Label {
+                elide: Text.ElideRight
+                text: webView.mediaSession.artist.length > 0
+                    ? qsTr("%1 - %2").arg(webView.mediaSession.artist).arg(webView.mediaSession.title)
+                    : webView.mediaSession.title
+                Layout.maximumWidth: 240
+            }
+
+            Button {
+                text: webView.mediaSession.playbackState === ServoMediaSession.Playing ? qsTr("Pause") : qsTr("Play")
+                onClicked: {
+                    if (webView.mediaSession.playbackState === ServoMediaSession.Playing) {
+                        webView.mediaSession.pause();
+                    } else {
+                        webView.mediaSession.play();
+                    }
+                }
+            }
+
+            Button {
+                text: qsTr("Stop")
+                onClicked: webView.mediaSession.stop()
+            }
+
+            Button {
+                checkable: true
+                checked: webView.audioMuted
+                text: qsTr("Mute")
+                onToggled: webView.audioMuted = checked
+            }
+        }
+    }
+
+    Shortcut {
+        enabled: webView.isFullScreen
+        sequence: StandardKey.Cancel
+        onActivated: webView.exitFullScreen()
     }
 
     // Progress bar at the bottom overlaying the Servo WebView
@@ -51,7 +195,167 @@ Window {
         anchors.left: parent.left
         anchors.right: parent.right
         height: 10
-        indeterminate: true
+        from: 0
+        to: 100
+        value: webView.loadProgress
         visible: webView.loading
     }
+
+    Dialog {
+        id: javaScriptDialog
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: request && request.dialogType === ServoJavaScriptDialog.Alert
+            ? Dialog.Ok : Dialog.Ok | Dialog.Cancel
+        title: qsTr("Message from page")
+        width: Math.min(parent.width - 48, 480)
+
+        property ServoJavaScriptDialog request: null
+
+        function openRequest(dialog) {
+            request = dialog;
+            promptField.text = dialog.defaultText;
+            open();
+        }
+
+        ColumnLayout {
+            anchors.fill: parent
+
+            Label {
+                text: javaScriptDialog.request ? javaScriptDialog.request.message : ""
+                wrapMode: Text.Wrap
+                Layout.fillWidth: true
+            }
+
+            TextField {
+                id: promptField
+                visible: javaScriptDialog.request
+                    && javaScriptDialog.request.dialogType === ServoJavaScriptDialog.Prompt
+                Layout.fillWidth: true
+            }
+        }
+
+        onAccepted: request.accept(promptField.text)
+        onRejected: request.reject()
+    }
+
+    Dialog {
+        id: authenticationDialog
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Ok | Dialog.Cancel
+        title: request && request.url.toString().length > 0
+            ? qsTr("Sign in to %1").arg(request.url.toString()) : qsTr("Sign in")
+        width: Math.min(parent.width - 48, 480)
+
+        property ServoAuthenticationRequest request: null
+
+        function openRequest(authenticationRequest) {
+            request = authenticationRequest;
+            userField.text = "";
+            passwordField.text = "";
+            open();
+        }
+
+        ColumnLayout {
+            anchors.fill: parent
+
+            TextField {
+                id: userField
+                placeholderText: qsTr("Username")
+                Layout.fillWidth: true
+            }
+
+            TextField {
+                id: passwordField
+                echoMode: TextInput.Password
+                placeholderText: qsTr("Password")
+                Layout.fillWidth: true
+            }
+        }
+
+        onAccepted: request.provide(userField.text, passwordField.text)
+        onRejected: request.cancel()
+    }
+
+    Dialog {
+        id: permissionDialog
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Yes | Dialog.No
+        title: qsTr("Permission request")
+        width: Math.min(parent.width - 48, 480)
+
+        property ServoPermissionRequest request: null
+
+        function openRequest(permissionRequest) {
+            request = permissionRequest;
+            open();
+        }
+
+        Label {
+            anchors.fill: parent
+            text: permissionDialog.request
+                ? qsTr("%1 wants to use %2").arg(permissionDialog.request.origin.toString())
+                    .arg(permissionDialog.request.feature)
+                : ""
+            wrapMode: Text.Wrap
+        }
+
+        onAccepted: request.grant()
+        onRejected: request.deny()
+    }
+
+    FileDialog {
+        id: fileDialog
+        fileMode: request && request.allowMultiple ? FileDialog.OpenFiles : FileDialog.OpenFile
+        nameFilters: {
+            if (!request || request.acceptedFilters.length === 0) {
+                return [];
+            }
+            const patterns = request.acceptedFilters.map((filter) => "*." + filter.replace(/^\./, ""));
+            return [qsTr("Accepted files (%1)").arg(patterns.join(" ")), qsTr("All files (*)")];
+        }
+
+        property ServoFileSelectionRequest request: null
+
+        function openRequest(fileSelectionRequest) {
+            request = fileSelectionRequest;
+            open();
+        }
+
+        onAccepted: request.accept(selectedFiles.map((file) => file.toString()))
+        onRejected: request.cancel()
+    }
+
+    Dialog {
+        id: beforeUnloadDialog
+        anchors.centerIn: parent
+        modal: true
+        standardButtons: Dialog.Discard | Dialog.Cancel
+        title: qsTr("Leave page?")
+        width: Math.min(parent.width - 48, 480)
+
+        property ServoBeforeUnloadRequest request: null
+
+        function openRequest(beforeUnloadRequest) {
+            request = beforeUnloadRequest;
+            open();
+        }
+
+        Label {
+            anchors.fill: parent
+            text: qsTr("Changes that you made may not be saved.")
+            wrapMode: Text.Wrap
+        }
+
+        onDiscarded: {
+            request.accept();
+            close();
+        }
+        onRejected: {
+            root.closeRequested = false;
+            request.cancel();
+        }
+    }
 }
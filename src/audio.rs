@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::collections::HashSet;
+
+use servo::msg::constellation_msg::PipelineId;
+use servo_media::{ClientContextId, ServoMedia};
+
+/// Script which mutes and scales the volume of the audio and video elements of the
+/// page, including those added later, or unmutes the elements which it muted
+///
+/// Elements which the page muted itself are left muted. The volume which the page gave
+/// an element when it was first seen is scaled, so a later change of the volume by the
+/// page is replaced.
+pub(crate) fn audio_script(muted: bool, volume: f64) -> String {
+    format!(
+        r#"(() => {{
+    const state = window.qtAudio
+        || (window.qtAudio = {{ elements: new Set(), volumes: new WeakMap() }});
+    state.muted = {muted};
+    state.volume = {volume};
+    const apply = (element) => {{
+        if (state.muted && !element.muted) {{
+            element.muted = true;
+            state.elements.add(element);
+        }}
+        if (!state.volumes.has(element)) {{
+            state.volumes.set(element, element.volume);
+        }}
+        element.volume = state.volumes.get(element) * state.volume;
+    }};
+    const applyAll = () => document.querySelectorAll("audio, video").forEach(apply);
+
+    applyAll();
+    if (state.muted || state.volume !== 1) {{
+        if (!state.observer) {{
+            state.observer = new MutationObserver(applyAll);
+            state.observer.observe(document, {{ childList: true, subtree: true }});
+        }}
+    }} else if (state.observer) {{
+        state.observer.disconnect();
+        state.observer = null;
+    }}
+    if (!state.muted) {{
+        state.elements.forEach((element) => {{
+            element.muted = false;
+        }});
+        state.elements.clear();
+    }}
+}})()"#
+    )
+}
+
+/// Mutes the audio of the pipelines of a web view through servo-media
+///
+/// TODO: Servo only tells the embedder about pipelines which the page navigates
+/// itself, so the media elements of the page are also muted with audio_script. Web
+/// Audio in other pipelines is not muted. servo-media has no volume for a client
+/// context, so the volume only applies to media elements through audio_script.
+#[derive(Default)]
+pub(crate) struct QServoAudio {
+    muted: bool,
+    /// Pipelines which could have media players or audio contexts
+    pipelines: HashSet<PipelineId>,
+}
+
+impl QServoAudio {
+    fn apply(&self, pipeline_id: &PipelineId) {
+        // This is how script builds the client context of the media in a pipeline
+        let client_context_id =
+            ClientContextId::build(pipeline_id.namespace_id.0, pipeline_id.index.0.get());
+        match ServoMedia::get() {
+            Ok(servo_media) => servo_media.mute(&client_context_id, self.muted),
+            Err(err) => println!("failed to get servo media: {err:?}"),
+        }
+    }
+
+    /// Track a pipeline of the web view so that muting applies to it
+    pub(crate) fn add_pipeline(&mut self, pipeline_id: PipelineId) {
+        if self.pipelines.insert(pipeline_id) && self.muted {
+            self.apply(&pipeline_id);
+        }
+    }
+
+    pub(crate) fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+
+        for pipeline_id in self.pipelines.iter() {
+            self.apply(pipeline_id);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servoauthenticationrequest")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("cxx-qt-lib/qurl.h");
+        type QUrl = cxx_qt_lib::QUrl;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_authentication_request_unique_ptr() -> UniquePtr<ServoAuthenticationRequest>;
+    }
+
+    unsafe extern "RustQt" {
+        /// Credentials asked for by the URL
+        ///
+        /// Servo does not tell us which URL a challenge is for, so the URL is only known
+        /// while a document is being fetched, for a resource of the page it is empty.
+        /// Servo does not tell us the realm either.
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QUrl, url)]
+        type ServoAuthenticationRequest = super::QServoAuthenticationRequestRust;
+
+        #[qinvokable]
+        fn provide(self: Pin<&mut ServoAuthenticationRequest>, user: &QString, password: &QString);
+
+        #[qinvokable]
+        fn cancel(self: Pin<&mut ServoAuthenticationRequest>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoAuthenticationRequest {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::{QString, QUrl};
+use servo::{embedder_traits::PromptCredentialsInput, ipc_channel::ipc::IpcSender};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+/// Credentials which are answered without asking Qt, keyed by the origin of the URL
+///
+/// Clones share the same credentials, so they can be changed after being given to the
+/// Servo thread.
+#[derive(Clone, Default)]
+pub(crate) struct QServoCredentialStore {
+    credentials: Arc<Mutex<HashMap<String, (String, String)>>>,
+}
+
+impl QServoCredentialStore {
+    pub(crate) fn insert(&self, url: &Url, user: impl Into<String>, password: impl Into<String>) {
+        self.credentials.lock().unwrap().insert(
+            url.origin().ascii_serialization(),
+            (user.into(), password.into()),
+        );
+    }
+
+    pub(crate) fn clear(&self) {
+        self.credentials.lock().unwrap().clear();
+    }
+
+    pub(crate) fn lookup(&self, url: &Url) -> Option<(String, String)> {
+        self.credentials
+            .lock()
+            .unwrap()
+            .get(&url.origin().ascii_serialization())
+            .cloned()
+    }
+}
+
+/// Credentials requested by Servo which have not been shown to Qt yet
+pub(crate) struct QServoAuthenticationRequest {
+    pub(crate) url: Option<Url>,
+    sender: IpcSender<PromptCredentialsInput>,
+}
+
+impl QServoAuthenticationRequest {
+    pub(crate) fn new(url: Option<Url>, sender: IpcSender<PromptCredentialsInput>) -> Self {
+        Self { url, sender }
+    }
+
+    pub(crate) fn provide(self, user: String, password: String) {
+        respond(&self.sender, Some(user), Some(password));
+    }
+
+    /// Answer with no credentials, used when there is no handler in Qt
+    pub(crate) fn cancel(self) {
+        respond(&self.sender, None, None);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoAuthenticationRequest> {
+        let mut request = qobject::servo_authentication_request_unique_ptr();
+        if let Some(url) = self.url.as_ref() {
+            request.pin_mut().set_url(QUrl::from(url));
+        }
+        request.pin_mut().rust_mut().sender = Some(self.sender);
+        request
+    }
+}
+
+fn respond(
+    sender: &IpcSender<PromptCredentialsInput>,
+    username: Option<String>,
+    password: Option<String>,
+) {
+    if let Err(err) = sender.send(PromptCredentialsInput { username, password }) {
+        println!("failed to answer authentication request: {err:?}");
+    }
+}
+
+#[derive(Default)]
+pub struct QServoAuthenticationRequestRust {
+    url: QUrl,
+    sender: Option<IpcSender<PromptCredentialsInput>>,
+}
+
+impl Drop for QServoAuthenticationRequestRust {
+    fn drop(&mut self) {
+        // Never leave the load waiting for an answer
+        if let Some(sender) = self.sender.take() {
+            respond(&sender, None, None);
+        }
+    }
+}
+
+impl qobject::ServoAuthenticationRequest {
+    /// Whether the request has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.sender.is_none()
+    }
+
+    fn provide(mut self: Pin<&mut Self>, user: &QString, password: &QString) {
+        if let Some(sender) = self.as_mut().rust_mut().sender.take() {
+            respond(
+                &sender,
+                Some(String::from(user)),
+                Some(String::from(password)),
+            );
+        }
+    }
+
+    fn cancel(mut self: Pin<&mut Self>) {
+        if let Some(sender) = self.as_mut().rust_mut().sender.take() {
+            respond(&sender, None, None);
+        }
+    }
+}
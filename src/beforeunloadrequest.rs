@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servobeforeunloadrequest")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qurl.h");
+        type QUrl = cxx_qt_lib::QUrl;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_before_unload_request_unique_ptr() -> UniquePtr<ServoBeforeUnloadRequest>;
+    }
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QUrl, url)]
+        type ServoBeforeUnloadRequest = super::QServoBeforeUnloadRequestRust;
+
+        /// Leave the page, discarding any unsaved changes
+        #[qinvokable]
+        fn accept(self: Pin<&mut ServoBeforeUnloadRequest>);
+
+        /// Stay on the page
+        #[qinvokable]
+        fn cancel(self: Pin<&mut ServoBeforeUnloadRequest>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoBeforeUnloadRequest {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::QUrl;
+use servo::ipc_channel::ipc::IpcSender;
+use url::Url;
+
+fn respond(sender: IpcSender<bool>, allow_unload: bool) {
+    if let Err(err) = sender.send(allow_unload) {
+        println!("failed to answer before unload request: {err:?}");
+    }
+}
+
+/// A beforeunload confirmation which has not been shown to Qt yet
+pub(crate) struct QServoBeforeUnloadRequest {
+    url: Option<Url>,
+    sender: IpcSender<bool>,
+}
+
+impl QServoBeforeUnloadRequest {
+    pub(crate) fn new(url: Option<Url>, sender: IpcSender<bool>) -> Self {
+        Self { url, sender }
+    }
+
+    /// Allow the page to unload, used when there is no handler in Qt
+    pub(crate) fn accept(self) {
+        respond(self.sender, true);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoBeforeUnloadRequest> {
+        let mut request = qobject::servo_before_unload_request_unique_ptr();
+        if let Some(url) = self.url.as_ref() {
+            request.pin_mut().set_url(QUrl::from(url));
+        }
+        request.pin_mut().rust_mut().sender = Some(self.sender);
+        request
+    }
+}
+
+#[derive(Default)]
+pub struct QServoBeforeUnloadRequestRust {
+    url: QUrl,
+    sender: Option<IpcSender<bool>>,
+}
+
+impl Drop for QServoBeforeUnloadRequestRust {
+    fn drop(&mut self) {
+        // Never leave the page waiting for an answer, browsers unload when unanswered
+        if let Some(sender) = self.sender.take() {
+            respond(sender, true);
+        }
+    }
+}
+
+impl qobject::ServoBeforeUnloadRequest {
+    /// Whether the request has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.sender.is_none()
+    }
+
+    fn accept(mut self: Pin<&mut Self>) {
+        if let Some(sender) = self.as_mut().rust_mut().sender.take() {
+            respond(sender, true);
+        }
+    }
+
+    fn cancel(mut self: Pin<&mut Self>) {
+        if let Some(sender) = self.as_mut().rust_mut().sender.take() {
+            respond(sender, false);
+        }
+    }
+}
@@ -4,25 +4,142 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use servo::{
-    compositing::windowing::EmbedderEvent, embedder_traits::EmbedderMsg,
+    compositing::windowing::EmbedderEvent,
+    embedder_traits::{EmbedderMsg, EventLoopWaker, MediaSessionEvent, PromptDefinition},
+    msg::constellation_msg::PipelineId,
+    servo_url::ServoUrl,
     TopLevelBrowsingContextId as WebViewId,
 };
-use std::collections::HashMap;
+use serde_json::json;
 use std::vec::Drain;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    audio::audio_script,
+    authenticationrequest::{QServoAuthenticationRequest, QServoCredentialStore},
+    beforeunloadrequest::QServoBeforeUnloadRequest,
+    content::QServoContent,
+    errorpage::QServoErrorPage,
+    fileselectionrequest::QServoFileSelectionRequest,
+    javascript::{execute_script, QServoJavaScriptResult},
+    javascriptdialog::QServoJavaScriptDialogRequest,
+    loadstatus::{
+        QServoLoadOutcome, QServoLoadProgress, LOAD_OUTCOME_SCRIPT, LOAD_PROGRESS_SCRIPT,
+    },
+    messagechannel::QServoMessageChannel,
+    permissionrequest::{QServoPermissionRequest, QServoPermissionStore},
+    urlscheme::QServoUrlSchemeHandlers,
+    webresourcerequest::{
+        QServoCannedResponses, QServoRequestInterceptor, QServoWebResourceRequestPending,
+    },
+    webview::qobject::{ErrorDomain, LoadStatus},
+};
 
 #[derive(Default)]
 pub struct WebView {}
 
+/// A request from the page to move or resize the window
+pub(crate) enum QServoGeometryRequest {
+    Move(i32, i32),
+    Resize(i32, i32),
+}
+
+pub(crate) struct QServoLoadRequest {
+    pub(crate) status: LoadStatus,
+    pub(crate) url: Option<url::Url>,
+    pub(crate) error_domain: ErrorDomain,
+    pub(crate) error_string: String,
+    /// The HTTP status of the document, or 0 if it is not known
+    pub(crate) http_status_code: u16,
+}
+
+impl QServoLoadRequest {
+    fn new(status: LoadStatus, url: Option<url::Url>) -> Self {
+        Self {
+            status,
+            url,
+            error_domain: ErrorDomain::NoErrorDomain,
+            error_string: String::default(),
+            http_status_code: 0,
+        }
+    }
+
+    fn failed(url: Option<url::Url>, error_domain: ErrorDomain, error_string: String) -> Self {
+        Self {
+            status: LoadStatus::LoadFailedStatus,
+            url,
+            error_domain,
+            error_string,
+            http_status_code: 0,
+        }
+    }
+
+    /// The load request as an object with status, url, errorDomain, errorString and
+    /// httpStatusCode
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "status": self.status.repr,
+            "url": self.url.as_ref().map(url::Url::as_str).unwrap_or_default(),
+            "errorDomain": self.error_domain.repr,
+            "errorString": self.error_string,
+            "httpStatusCode": self.http_status_code,
+        })
+        .to_string()
+    }
+}
+
+/// An answer from the page about a load, tagged with the id of the load
+enum QServoLoadUpdate {
+    /// How many resources of the document have finished
+    Progress(u64, usize),
+    /// The load has finished, with why it failed if it did
+    Outcome(u64, QServoLoadOutcome),
+}
+
+/// The URL of the page which Servo shows instead of a crashed page
+const SERVO_FAILURE_URL: &str = "about:failure";
+
+/// The content of the page crashed
+pub(crate) struct QServoTermination {
+    pub(crate) reason: String,
+    pub(crate) backtrace: String,
+    /// Whether the last frame should be kept, as no error page is shown
+    pub(crate) keep_frame: bool,
+}
+
 #[derive(Default)]
 pub(crate) struct QServoBrowserResponse {
     pub(crate) favicon_url: Option<url::Url>,
     pub(crate) present: Option<bool>,
     pub(crate) title: Option<String>,
+    /// The status text, which Servo uses for the link under the pointer
+    pub(crate) status_text: Option<Option<String>>,
     pub(crate) loading: Option<bool>,
+    pub(crate) load_progress: Option<i32>,
+    pub(crate) load_requests: Vec<QServoLoadRequest>,
     pub(crate) url: Option<url::Url>,
     pub(crate) blocked_navigation_request: Option<url::Url>,
     pub(crate) can_go_back: Option<bool>,
     pub(crate) can_go_forward: Option<bool>,
+    pub(crate) full_screen: Option<bool>,
+    pub(crate) geometry_requests: Vec<QServoGeometryRequest>,
+    pub(crate) media_session_events: Vec<MediaSessionEvent>,
+    /// Pipelines which have been seen, so that audio settings can be applied
+    pub(crate) pipelines: Vec<PipelineId>,
+    /// Messages from window.qt.postMessage with their origin and JSON data
+    pub(crate) messages: Vec<(String, String)>,
+    pub(crate) terminated: Option<QServoTermination>,
+    /// The web view was closed, for example by window.close()
+    pub(crate) webview_closed: bool,
+    pub(crate) javascript_dialogs: Vec<QServoJavaScriptDialogRequest>,
+    pub(crate) web_resource_requests: Vec<QServoWebResourceRequestPending>,
+    pub(crate) authentication_requests: Vec<QServoAuthenticationRequest>,
+    pub(crate) before_unload_requests: Vec<QServoBeforeUnloadRequest>,
+    pub(crate) permission_requests: Vec<QServoPermissionRequest>,
+    pub(crate) file_selection_requests: Vec<QServoFileSelectionRequest>,
 }
 
 #[derive(Default)]
@@ -30,13 +147,354 @@ pub(crate) struct QServoBrowser {
     web_views: HashMap<WebViewId, WebView>,
     event_queue: Vec<EmbedderEvent>,
     focused_webview_id: Option<WebViewId>,
+    /// The URL of the current or last load, used for reporting load status
+    load_url: Option<url::Url>,
+    /// The URL of the document in the top level frame, unlike load_url this does not
+    /// change until a navigation has committed
+    document_url: Option<url::Url>,
+    /// Whether the current load is in progress and has not been stopped
+    load_active: bool,
+    /// Increases with every load, so that answers about an older load are ignored
+    load_id: u64,
+    load_progress: QServoLoadProgress,
+    /// Whether the page has been asked how many resources have finished
+    load_progress_pending: bool,
+    /// Answers from the page, which arrive on Servo's IPC router thread
+    load_updates: Arc<Mutex<Vec<QServoLoadUpdate>>>,
+    /// Wakes Qt so that answers from the page are handled
+    event_loop_waker: Option<Box<dyn EventLoopWaker>>,
+    error_page: Option<QServoErrorPage>,
+    /// The data URL of a shown error page and the failed URL it stands in for
+    error_page_url: Option<(url::Url, url::Url)>,
+    /// The data URL of loaded content and the base URL it is reported as
+    content_url: Option<(url::Url, url::Url)>,
+    /// Whether the error page is loading, so that its load is not reported
+    error_page_loading: bool,
+    credential_store: Option<QServoCredentialStore>,
+    /// The URL of the document being fetched until its head is parsed, an
+    /// authentication challenge in that time is for the document
+    document_request_url: Option<url::Url>,
+    permission_store: Option<QServoPermissionStore>,
+    /// Files which answer file selections without asking Qt
+    file_chooser_result: Option<Vec<String>>,
+    /// The content crashed, until Servo has loaded its own failure page
+    terminated: bool,
+    /// Whether the media elements of pages are muted
+    audio_muted: bool,
+    /// The volume of the media elements of pages, from 0 to 1
+    audio_volume: f64,
+    /// The URL which the page navigates to once it has agreed to be closed
+    close_url: Option<url::Url>,
+    /// Increases with every close, so that a cancelled close is not mistaken for another
+    close_id: u64,
+    message_channel: QServoMessageChannel,
+    url_scheme_handlers: QServoUrlSchemeHandlers,
+    canned_responses: QServoCannedResponses,
+    request_interceptor: Option<Arc<dyn QServoRequestInterceptor>>,
+    /// Whether QML has a handler for web resource requests
+    intercept_requests_in_qml: bool,
 }
 
 impl QServoBrowser {
+    pub fn new(event_loop_waker: Box<dyn EventLoopWaker>) -> Self {
+        Self {
+            event_loop_waker: Some(event_loop_waker),
+            audio_volume: 1.0,
+            ..Default::default()
+        }
+    }
+
     pub fn webview_id(&self) -> Option<WebViewId> {
         self.focused_webview_id
     }
 
+    /// The URL of the document in the top level frame
+    pub fn document_url(&self) -> Option<&url::Url> {
+        self.document_url.as_ref()
+    }
+
+    pub fn set_credential_store(&mut self, credential_store: Option<QServoCredentialStore>) {
+        self.credential_store = credential_store;
+    }
+
+    pub fn set_request_interception(
+        &mut self,
+        interceptor: Option<Arc<dyn QServoRequestInterceptor>>,
+        in_qml: bool,
+    ) {
+        self.request_interceptor = interceptor;
+        self.intercept_requests_in_qml = in_qml;
+    }
+
+    pub fn set_url_scheme_handlers(&mut self, handlers: QServoUrlSchemeHandlers) {
+        self.url_scheme_handlers = handlers;
+    }
+
+    pub fn set_canned_responses(&mut self, responses: QServoCannedResponses) {
+        self.canned_responses = responses;
+    }
+
+    /// Remember which URL loaded content is reported as, returns the URL to load
+    pub fn load_content(&mut self, content: QServoContent) -> ServoUrl {
+        self.content_url = Some((content.data_url.as_url().to_owned(), content.base_url));
+        content.data_url
+    }
+
+    pub fn set_message_channel(&mut self, message_channel: QServoMessageChannel) {
+        self.message_channel = message_channel;
+    }
+
+    /// Run a script in the page if the message channel is open to it
+    fn execute_message_channel_script(&mut self, script: String) {
+        let allowed = self
+            .document_url
+            .as_ref()
+            .and_then(|url| self.message_channel.allowed_origin(url))
+            .is_some();
+        if let (true, Some(webview_id)) = (allowed, self.focused_webview_id) {
+            match execute_script(webview_id, script, |_| {}) {
+                Ok(event) => self.event_queue.push(event),
+                Err(err) => println!("failed to run message channel script: {err}"),
+            }
+        }
+    }
+
+    /// Mute and set the volume of the media elements of the page, see QServoAudio for
+    /// the rest
+    pub fn set_audio(&mut self, muted: bool, volume: f64) {
+        let volume = if volume.is_nan() {
+            1.0
+        } else {
+            volume.clamp(0.0, 1.0)
+        };
+        if self.audio_muted != muted || self.audio_volume != volume {
+            self.audio_muted = muted;
+            self.audio_volume = volume;
+            self.execute_audio_script();
+        }
+    }
+
+    /// Whether the page needs audio_script, as pages start unmuted at full volume
+    fn audio_changed(&self) -> bool {
+        self.audio_muted || self.audio_volume != 1.0
+    }
+
+    fn execute_audio_script(&mut self) {
+        if let Some(webview_id) = self.focused_webview_id {
+            let script = audio_script(self.audio_muted, self.audio_volume);
+            match execute_script(webview_id, script, |_| {}) {
+                Ok(event) => self.event_queue.push(event),
+                Err(err) => println!("failed to set the audio of the page: {err}"),
+            }
+        }
+    }
+
+    /// Close the web view once the page agrees, returns false if there is none
+    ///
+    /// Servo only runs beforeunload handlers when the page navigates itself away, not
+    /// when a web view is closed, so the page is navigated to a URL which closes it.
+    pub fn close(&mut self) -> bool {
+        let Some(webview_id) = self.focused_webview_id else {
+            return false;
+        };
+
+        // A blank page has nothing to confirm, and navigating it would only change
+        // its fragment
+        let blank = self
+            .document_url
+            .as_ref()
+            .map_or(true, |url| url.as_str().starts_with("about:blank"));
+        if blank {
+            self.event_queue.push(EmbedderEvent::CloseWebView(webview_id));
+            return true;
+        }
+
+        self.close_id += 1;
+        let close_url = format!("about:blank#close-{}", self.close_id);
+        let script = format!("window.location.replace(\"{close_url}\");");
+        self.close_url = url::Url::parse(&close_url).ok();
+
+        match execute_script(webview_id, script, |_| {}) {
+            Ok(event) => self.event_queue.push(event),
+            Err(err) => {
+                println!("failed to ask the page to close: {err}");
+                self.close_url = None;
+                self.event_queue.push(EmbedderEvent::CloseWebView(webview_id));
+            }
+        }
+        true
+    }
+
+    /// Enter or leave fullscreen in the page, it enters with the document element
+    pub fn set_full_screen(&mut self, full_screen: bool) {
+        let Some(webview_id) = self.focused_webview_id else {
+            return;
+        };
+
+        if !full_screen {
+            self.event_queue.push(EmbedderEvent::ExitFullScreen(webview_id));
+            return;
+        }
+
+        let script = "void document.documentElement.requestFullscreen();".to_owned();
+        match execute_script(webview_id, script, |_| {}) {
+            Ok(event) => self.event_queue.push(event),
+            Err(err) => println!("failed to enter fullscreen: {err}"),
+        }
+    }
+
+    /// Dispatch a message event with the JSON data to window.qt in the page
+    pub fn post_message(&mut self, json: &str) {
+        self.execute_message_channel_script(QServoMessageChannel::post_message_script(json));
+    }
+
+    /// Ask the page about the current load, the answer is handled with the next events
+    fn query_load(
+        &mut self,
+        script: &str,
+        update: impl FnOnce(u64, QServoJavaScriptResult) -> QServoLoadUpdate + Send + 'static,
+    ) -> Result<(), String> {
+        let webview_id = self
+            .focused_webview_id
+            .ok_or_else(|| "There is no page".to_owned())?;
+        let load_id = self.load_id;
+        let load_updates = self.load_updates.clone();
+        let event_loop_waker = self.event_loop_waker.as_ref().map(|waker| waker.clone_box());
+        let event = execute_script(webview_id, script.to_owned(), move |result| {
+            load_updates.lock().unwrap().push(update(load_id, result));
+            if let Some(event_loop_waker) = event_loop_waker {
+                event_loop_waker.wake();
+            }
+        })?;
+        self.event_queue.push(event);
+        Ok(())
+    }
+
+    /// Handle the answers from the page about loads
+    fn handle_load_updates(&mut self, response: &mut QServoBrowserResponse) {
+        let updates = std::mem::take(&mut *self.load_updates.lock().unwrap());
+        for update in updates {
+            match update {
+                QServoLoadUpdate::Progress(load_id, finished) => {
+                    if load_id == self.load_id {
+                        self.load_progress_pending = false;
+                        if self.load_active {
+                            response.load_progress =
+                                Some(self.load_progress.resources_finished(finished));
+                        }
+                    }
+                }
+                QServoLoadUpdate::Outcome(load_id, _) if load_id != self.load_id => {}
+                QServoLoadUpdate::Outcome(_, _) if !self.load_active => {}
+                QServoLoadUpdate::Outcome(_, outcome) => match outcome.failure {
+                    Some((error_domain, error_string)) => {
+                        self.load_failed(response, error_domain, error_string);
+                    }
+                    None => self.load_succeeded(response, outcome.http_status_code),
+                },
+            }
+        }
+    }
+
+    fn load_succeeded(&mut self, response: &mut QServoBrowserResponse, http_status_code: u16) {
+        self.load_active = false;
+        response.loading = Some(false);
+        response.load_progress = Some(100);
+        response.load_requests.push(QServoLoadRequest {
+            http_status_code,
+            ..QServoLoadRequest::new(LoadStatus::LoadSucceededStatus, self.load_url.clone())
+        });
+    }
+
+    pub fn set_file_chooser_result(&mut self, files: Option<Vec<String>>) {
+        self.file_chooser_result = files;
+    }
+
+    pub fn set_permission_store(&mut self, permission_store: Option<QServoPermissionStore>) {
+        self.permission_store = permission_store;
+    }
+
+    pub fn set_error_page(&mut self, error_page: Option<QServoErrorPage>) {
+        self.error_page = error_page;
+    }
+
+    /// Map the data URL of a shown error page back to the URL which failed, and
+    /// the data URL of loaded content back to its base URL
+    fn display_url(&self, url: url::Url) -> url::Url {
+        if let Some((_, failed_url)) = self
+            .error_page_url
+            .as_ref()
+            .filter(|(error_page_url, _)| *error_page_url == url)
+        {
+            return failed_url.to_owned();
+        }
+
+        if let Some((_, base_url)) = self
+            .content_url
+            .as_ref()
+            .filter(|(data_url, _)| *data_url == url)
+        {
+            return base_url.to_owned();
+        }
+
+        url
+    }
+
+    /// Report a failed load and show the custom error page if there is one
+    fn load_failed(
+        &mut self,
+        response: &mut QServoBrowserResponse,
+        error_domain: ErrorDomain,
+        error_string: String,
+    ) {
+        self.load_active = false;
+        response.loading = Some(false);
+
+        if let (Some(error_page), Some(webview_id), Some(url)) = (
+            self.error_page.as_ref(),
+            self.focused_webview_id,
+            self.load_url.as_ref(),
+        ) {
+            match error_page.to_data_url(url, error_domain, &error_string) {
+                Ok(data_url) => {
+                    self.error_page_url = Some((data_url.as_url().to_owned(), url.to_owned()));
+                    self.event_queue
+                        .push(EmbedderEvent::LoadUrl(webview_id, data_url));
+                }
+                Err(err) => println!("failed to show the error page: {err}"),
+            }
+        }
+
+        response.load_requests.push(QServoLoadRequest::failed(
+            self.load_url.clone(),
+            error_domain,
+            error_string,
+        ));
+    }
+
+    /// Stop the current load, returns the stopped request if there was one.
+    pub fn stop_load(&mut self) -> Option<QServoLoadRequest> {
+        if !std::mem::take(&mut self.load_active) {
+            return None;
+        }
+
+        // window.stop() aborts the fetches and the parser of the document
+        //
+        // TODO: Servo has no way to cancel a navigation before its document replaces
+        // the page, so such a navigation still completes
+        if let Some(webview_id) = self.focused_webview_id {
+            match execute_script(webview_id, "window.stop();".to_owned(), |_| {}) {
+                Ok(event) => self.event_queue.push(event),
+                Err(err) => println!("failed to stop the load: {err}"),
+            }
+        }
+
+        Some(QServoLoadRequest::new(
+            LoadStatus::LoadStoppedStatus,
+            self.load_url.clone(),
+        ))
+    }
+
     pub fn get_events(&mut self) -> Vec<EmbedderEvent> {
         std::mem::take(&mut self.event_queue)
     }
@@ -50,9 +508,23 @@ impl QServoBrowser {
         navigation_allowed: bool,
     ) -> QServoBrowserResponse {
         let mut response = QServoBrowserResponse::default();
+        self.handle_load_updates(&mut response);
 
+        let mut had_events = false;
         for (webview_id, msg) in events {
+            had_events = true;
             match msg {
+                // Servo replaces a crashed page with its own failure page, which is
+                // not reported, after that loads are reported again
+                EmbedderMsg::HistoryChanged(urls, position)
+                    if urls[position].as_str() == SERVO_FAILURE_URL => {}
+                EmbedderMsg::LoadComplete if self.terminated => {
+                    self.terminated = false;
+                }
+                EmbedderMsg::ChangePageTitle(..)
+                | EmbedderMsg::LoadStart
+                | EmbedderMsg::HeadParsed
+                    if self.terminated => {}
                 // Do not allow for opening a new tab / window
                 // Not handling this crashes the webview
                 EmbedderMsg::AllowOpeningWebView(ipc) => {
@@ -60,17 +532,50 @@ impl QServoBrowser {
                 }
                 EmbedderMsg::AllowNavigationRequest(pipeline_id, url) => {
                     if let Some(_webview_id) = webview_id {
+                        response.pipelines.push(pipeline_id);
+
+                        let url = url.into_url();
+
+                        // The page agreed to be closed, so close it instead of navigating
+                        if self.close_url.as_ref() == Some(&url) {
+                            self.close_url = None;
+                            self.event_queue
+                                .push(EmbedderEvent::AllowNavigationResponse(pipeline_id, false));
+                            if let Some(webview_id) = self.focused_webview_id {
+                                self.event_queue.push(EmbedderEvent::CloseWebView(webview_id));
+                            }
+                            continue;
+                        }
+
+                        // Our own error page is always allowed
+                        self.error_page_loading = self
+                            .error_page_url
+                            .as_ref()
+                            .is_some_and(|(error_page_url, _)| *error_page_url == url);
+                        let navigation_allowed = navigation_allowed || self.error_page_loading;
+
                         self.event_queue
                             .push(EmbedderEvent::AllowNavigationResponse(
                                 pipeline_id,
                                 navigation_allowed,
                             ));
 
-                        let url = url.into_url();
+                        let url = self.display_url(url);
                         if navigation_allowed {
                             // There is a new URL
+                            self.load_url = Some(url.clone());
                             response.url = Some(url);
                         } else {
+                            // The blocked load is reported as started and failed at once
+                            response.load_requests.push(QServoLoadRequest::new(
+                                LoadStatus::LoadStartedStatus,
+                                Some(url.clone()),
+                            ));
+                            response.load_requests.push(QServoLoadRequest::failed(
+                                Some(url.clone()),
+                                ErrorDomain::NavigationErrorDomain,
+                                "Navigation is not allowed".to_owned(),
+                            ));
                             response.blocked_navigation_request = Some(url);
                         }
                     }
@@ -83,6 +588,15 @@ impl QServoBrowser {
                 EmbedderMsg::WebViewClosed(webview_id) => {
                     self.web_views.remove(&webview_id);
                     self.focused_webview_id = None;
+
+                    // Forget the state of the closed web view
+                    self.load_active = false;
+                    self.load_url = None;
+                    self.document_url = None;
+                    self.error_page_url = None;
+                    self.error_page_loading = false;
+                    response.loading = Some(false);
+                    response.webview_closed = true;
                 }
                 EmbedderMsg::WebViewFocused(webview_id) => {
                     self.focused_webview_id = Some(webview_id);
@@ -90,26 +604,222 @@ impl QServoBrowser {
                 EmbedderMsg::WebViewBlurred => {
                     self.focused_webview_id = None;
                 }
+                EmbedderMsg::Status(status_text) => {
+                    response.status_text = Some(status_text);
+                }
                 EmbedderMsg::ChangePageTitle(title) => {
                     response.title = title;
                 }
                 EmbedderMsg::NewFavicon(url) => {
                     response.favicon_url = Some(url.as_url().to_owned());
                 }
+                EmbedderMsg::LoadStart if self.error_page_loading => {}
+                EmbedderMsg::HeadParsed if self.error_page_loading => {}
+                EmbedderMsg::LoadComplete if self.error_page_loading => {
+                    self.error_page_loading = false;
+                }
                 EmbedderMsg::LoadStart => {
+                    self.load_active = true;
+                    self.load_id += 1;
+                    self.load_progress_pending = false;
                     response.loading = Some(true);
+                    response.load_progress = Some(self.load_progress.start());
+                    response.load_requests.push(QServoLoadRequest::new(
+                        LoadStatus::LoadStartedStatus,
+                        self.load_url.clone(),
+                    ));
+                }
+                EmbedderMsg::HeadParsed => {
+                    self.document_request_url = None;
+                    if self.load_active {
+                        response.load_progress = Some(self.load_progress.head_parsed());
+                    }
+                    self.execute_message_channel_script(QServoMessageChannel::injection_script());
+                    if self.audio_changed() {
+                        self.execute_audio_script();
+                    }
                 }
                 EmbedderMsg::LoadComplete => {
-                    response.loading = Some(false);
+                    self.document_request_url = None;
+
+                    // Inject again in case the page had no head
+                    self.execute_message_channel_script(QServoMessageChannel::injection_script());
+                    if self.audio_changed() {
+                        self.execute_audio_script();
+                    }
+
+                    // A stopped load has already been reported to Qt, otherwise look
+                    // at the document to find whether it is an error page of Servo
+                    if self.load_active {
+                        let query = self.query_load(LOAD_OUTCOME_SCRIPT, |load_id, result| {
+                            let outcome = result
+                                .ok()
+                                .and_then(|json| serde_json::from_str(&json).ok())
+                                .map(|description| {
+                                    QServoLoadOutcome::from_description(&description)
+                                })
+                                .unwrap_or_default();
+                            QServoLoadUpdate::Outcome(load_id, outcome)
+                        });
+                        if let Err(err) = query {
+                            println!("failed to check the load: {err}");
+                            self.load_succeeded(&mut response, 0);
+                        }
+                    }
                 }
                 EmbedderMsg::ReadyToPresent => {
                     response.present = Some(true);
                 }
                 EmbedderMsg::HistoryChanged(urls, position) => {
-                    response.url = Some(urls[position].as_url().to_owned());
+                    self.load_url = Some(self.display_url(urls[position].as_url().to_owned()));
+                    self.document_url = self.load_url.clone();
+                    response.url = self.load_url.clone();
                     response.can_go_back = Some(position > 0);
                     response.can_go_forward = Some(position < (urls.len() - 1));
                 }
+                EmbedderMsg::AllowUnload(ipc) => {
+                    response
+                        .before_unload_requests
+                        .push(QServoBeforeUnloadRequest::new(self.load_url.clone(), ipc));
+                }
+                EmbedderMsg::Prompt(PromptDefinition::Input(message, data, ipc), _origin)
+                    if QServoMessageChannel::is_message_prompt(&message) =>
+                {
+                    let _ = ipc.send(None);
+                    // TODO: Servo does not tell us which frame sent the prompt so
+                    // assume that it is the document in the top level frame, any frame
+                    // in an allowed page can send messages as the page, see
+                    // QServoMessageChannel
+                    if let Some(origin) = self
+                        .document_url
+                        .as_ref()
+                        .and_then(|url| self.message_channel.allowed_origin(url))
+                    {
+                        response.messages.push((origin, data));
+                    }
+                }
+                EmbedderMsg::Prompt(definition, _origin) => {
+                    match QServoJavaScriptDialogRequest::from_prompt(definition) {
+                        Ok(request) => response.javascript_dialogs.push(request),
+                        Err(PromptDefinition::Credentials(ipc)) => {
+                            // TODO: Servo does not tell us which URL the challenge is for,
+                            // outside of a document fetch it is for an unknown resource so
+                            // stored credentials are not given out
+                            let url = self.document_request_url.clone();
+                            let stored_credentials = self
+                                .credential_store
+                                .as_ref()
+                                .zip(url.as_ref())
+                                .and_then(|(store, url)| store.lookup(url));
+                            let request = QServoAuthenticationRequest::new(url, ipc);
+                            if let Some((user, password)) = stored_credentials {
+                                request.provide(user, password);
+                            } else {
+                                response.authentication_requests.push(request);
+                            }
+                        }
+                        Err(_others) => {}
+                    }
+                }
+                EmbedderMsg::WebResourceRequested(request, ipc) => {
+                    let request = QServoWebResourceRequestPending::new(request, ipc);
+                    if request.is_main_frame() {
+                        self.document_request_url = Some(request.url().clone());
+                    } else if self.load_active {
+                        self.load_progress.resource_requested();
+                    }
+
+                    // Registered schemes are answered by their handler instead, then
+                    // canned responses are used before the interceptor
+                    let action = self
+                        .url_scheme_handlers
+                        .action_for(request.method(), request.url())
+                        .or_else(|| self.canned_responses.action_for(request.url()));
+                    if let Some(action) = action {
+                        request.respond(action);
+                        continue;
+                    }
+
+                    let mut request = Some(request);
+                    if let Some(interceptor) = self.request_interceptor.as_ref() {
+                        request =
+                            request.and_then(|request| request.intercept(interceptor.as_ref()));
+                    }
+
+                    if let Some(request) = request {
+                        if self.intercept_requests_in_qml {
+                            response.web_resource_requests.push(request);
+                        } else {
+                            request.allow();
+                        }
+                    }
+                }
+                EmbedderMsg::PromptPermission(prompt, ipc) => {
+                    // TODO: Servo does not tell us which frame asked, so the
+                    // request is for the document in the top level frame
+                    let request = QServoPermissionRequest::new(
+                        prompt,
+                        self.document_url.as_ref(),
+                        ipc,
+                        self.permission_store.clone(),
+                    );
+                    if let Some(granted) = request.stored_decision() {
+                        request.respond(granted);
+                    } else {
+                        response.permission_requests.push(request);
+                    }
+                }
+                EmbedderMsg::MediaSessionEvent(event) => {
+                    response.media_session_events.push(event);
+                }
+                EmbedderMsg::MoveTo(point) => {
+                    response
+                        .geometry_requests
+                        .push(QServoGeometryRequest::Move(point.x, point.y));
+                }
+                EmbedderMsg::ResizeTo(size) => {
+                    response
+                        .geometry_requests
+                        .push(QServoGeometryRequest::Resize(size.width, size.height));
+                }
+                EmbedderMsg::SetFullscreenState(full_screen) => {
+                    response.full_screen = Some(full_screen);
+                }
+                EmbedderMsg::SelectFiles(filters, allow_multiple, ipc) => {
+                    let request = QServoFileSelectionRequest::new(filters, allow_multiple, ipc);
+                    if let Some(files) = self.file_chooser_result.as_ref() {
+                        request.respond(Some(files.to_owned()));
+                    } else {
+                        response.file_selection_requests.push(request);
+                    }
+                }
+                EmbedderMsg::Panic(reason, backtrace) => {
+                    println!("handle_servo_events: panic {reason}");
+                    self.terminated = true;
+
+                    // Show the error page instead of the crashed page, otherwise the
+                    // last frame is kept until the next load or a restart
+                    let show_error_page = self.error_page.is_some() && self.load_url.is_some();
+                    if show_error_page {
+                        self.load_failed(
+                            &mut response,
+                            ErrorDomain::InternalErrorDomain,
+                            reason.clone(),
+                        );
+                    } else if std::mem::take(&mut self.load_active) {
+                        response.loading = Some(false);
+                        response.load_requests.push(QServoLoadRequest::failed(
+                            self.load_url.clone(),
+                            ErrorDomain::InternalErrorDomain,
+                            reason.clone(),
+                        ));
+                    }
+                    response.terminated = Some(QServoTermination {
+                        reason,
+                        backtrace: backtrace.unwrap_or_default(),
+                        keep_frame: !show_error_page,
+                    });
+                }
                 // TODO: this is where page up/down or shortcuts are handled
                 // EmbedderMsg::Keyboard(key_event) => {}
                 _others => {
@@ -118,6 +828,25 @@ impl QServoBrowser {
             }
         }
 
+        // Ask the page how far the load is whenever Servo has done something
+        if had_events
+            && self.load_active
+            && !self.load_progress_pending
+            && self.load_progress.waiting_for_resources()
+        {
+            let query = self.query_load(LOAD_PROGRESS_SCRIPT, |load_id, result| {
+                let finished = result
+                    .ok()
+                    .and_then(|json| serde_json::from_str::<f64>(&json).ok())
+                    .unwrap_or(0.0) as usize;
+                QServoLoadUpdate::Progress(load_id, finished)
+            });
+            match query {
+                Ok(()) => self.load_progress_pending = true,
+                Err(err) => println!("failed to ask for the load progress: {err}"),
+            }
+        }
+
         response
     }
 
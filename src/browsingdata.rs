@@ -0,0 +1,181 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::{io::ErrorKind, path::Path};
+
+const HTTP_CACHE: &str = "httpCache";
+const COOKIES: &str = "cookies";
+const LOCAL_STORAGE: &str = "localStorage";
+const SESSION_STORAGE: &str = "sessionStorage";
+/// Servo has no IndexedDB yet and no way to clear the session history
+const UNSUPPORTED: [&str; 2] = ["indexedDB", "history"];
+
+/// The files in the config directory which Servo persists cookies and local storage into
+const COOKIE_JAR_FILE: &str = "cookie_jar.json";
+const LOCAL_STORAGE_FILE: &str = "local_data.json";
+
+/// The kinds of browsing data to clear, each is cleared for every site
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QServoBrowsingDataTypes {
+    pub http_cache: bool,
+    pub cookies: bool,
+    pub local_storage: bool,
+    pub session_storage: bool,
+}
+
+impl QServoBrowsingDataTypes {
+    /// The types from their names as used in QML, such as httpCache and cookies
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut types = Self::default();
+        for name in names {
+            match name {
+                HTTP_CACHE => types.http_cache = true,
+                COOKIES => types.cookies = true,
+                LOCAL_STORAGE => types.local_storage = true,
+                SESSION_STORAGE => types.session_storage = true,
+                name if UNSUPPORTED.contains(&name) => {
+                    return Err(format!("browsing data type is not supported by Servo: {name}"))
+                }
+                name => return Err(format!("unknown browsing data type: {name}")),
+            }
+        }
+        Ok(types)
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.http_cache, HTTP_CACHE),
+            (self.cookies, COOKIES),
+            (self.local_storage, LOCAL_STORAGE),
+            (self.session_storage, SESSION_STORAGE),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+
+    /// Whether Servo has to be restarted to clear the data
+    ///
+    /// Servo can only clear the HTTP cache while it runs. Cookies and storage are only
+    /// given to embedders for the current page, so they are cleared for every site by
+    /// shutting Servo down, which forgets what it keeps in memory.
+    pub(crate) fn needs_restart(&self) -> bool {
+        self.cookies || self.local_storage || self.session_storage
+    }
+
+    pub(crate) fn merge(&mut self, other: Self) {
+        self.http_cache |= other.http_cache;
+        self.cookies |= other.cookies;
+        self.local_storage |= other.local_storage;
+        self.session_storage |= other.session_storage;
+    }
+}
+
+/// Remove the file, returns whether it is gone
+fn remove_file(path: &Path) -> bool {
+    match std::fs::remove_file(path) {
+        Ok(()) => true,
+        Err(err) if err.kind() == ErrorKind::NotFound => true,
+        Err(err) => {
+            println!("failed to remove {path:?}: {err:?}");
+            false
+        }
+    }
+}
+
+/// Remove the data which Servo persisted into the config directory, returns the types
+/// which were cleared
+///
+/// Servo has to have been shut down, as it writes its state when it exits. Everything
+/// else is only kept in memory, so it is gone with Servo.
+pub(crate) fn remove_persisted_data(
+    config_dir: Option<&Path>,
+    types: QServoBrowsingDataTypes,
+) -> QServoBrowsingDataTypes {
+    let mut cleared = types;
+    if let Some(config_dir) = config_dir {
+        if types.cookies {
+            cleared.cookies = remove_file(&config_dir.join(COOKIE_JAR_FILE));
+        }
+        if types.local_storage {
+            cleared.local_storage = remove_file(&config_dir.join(LOCAL_STORAGE_FILE));
+        }
+    }
+    cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_names() {
+        let types = QServoBrowsingDataTypes::from_names(["cookies", "localStorage"]).unwrap();
+        assert_eq!(
+            types,
+            QServoBrowsingDataTypes {
+                cookies: true,
+                local_storage: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(types.names(), vec!["cookies", "localStorage"]);
+    }
+
+    #[test]
+    fn from_names_empty() {
+        let types = QServoBrowsingDataTypes::from_names([]).unwrap();
+        assert_eq!(types, QServoBrowsingDataTypes::default());
+        assert!(types.names().is_empty());
+    }
+
+    #[test]
+    fn from_names_unknown() {
+        assert!(QServoBrowsingDataTypes::from_names(["cookies", "passwords"]).is_err());
+    }
+
+    #[test]
+    fn from_names_unsupported() {
+        assert!(QServoBrowsingDataTypes::from_names(["indexedDB"]).is_err());
+        assert!(QServoBrowsingDataTypes::from_names(["history"]).is_err());
+    }
+
+    #[test]
+    fn needs_restart() {
+        let types = QServoBrowsingDataTypes {
+            http_cache: true,
+            ..Default::default()
+        };
+        assert!(!types.needs_restart());
+        assert!(QServoBrowsingDataTypes::from_names(["cookies"])
+            .unwrap()
+            .needs_restart());
+        assert!(QServoBrowsingDataTypes::from_names(["sessionStorage"])
+            .unwrap()
+            .needs_restart());
+    }
+
+    #[test]
+    fn remove_persisted_data() {
+        let dir = std::env::temp_dir()
+            .join(format!("cxxqtservo-browsingdata-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(COOKIE_JAR_FILE), "{}").unwrap();
+        std::fs::write(dir.join(LOCAL_STORAGE_FILE), "{}").unwrap();
+
+        let types = QServoBrowsingDataTypes::from_names(["cookies"]).unwrap();
+        assert_eq!(super::remove_persisted_data(Some(&dir), types), types);
+        assert!(!dir.join(COOKIE_JAR_FILE).exists());
+        assert!(dir.join(LOCAL_STORAGE_FILE).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remove_persisted_data_in_memory() {
+        let types = QServoBrowsingDataTypes::from_names(["cookies", "localStorage"]).unwrap();
+        assert_eq!(super::remove_persisted_data(None, types), types);
+    }
+}
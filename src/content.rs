@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use servo::servo_url::ServoUrl;
+use url::Url;
+
+/// Build a data URL of the given content
+///
+/// The MIME type is not percent-decoded by a data URL, so one which would end it early
+/// is refused.
+pub(crate) fn to_data_url(mime_type: &str, data: &[u8]) -> Result<ServoUrl, String> {
+    let valid = mime_type
+        .bytes()
+        .all(|byte| byte == b' ' || (byte.is_ascii_graphic() && !b",#%?".contains(&byte)));
+    if !valid {
+        return Err(format!("invalid MIME type: {mime_type:?}"));
+    }
+
+    let mut data_url = format!("data:{mime_type},");
+    for byte in data {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(byte) {
+            data_url.push(*byte as char);
+        } else {
+            data_url.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    ServoUrl::parse(&data_url).map_err(|err| format!("failed to build data URL: {err}"))
+}
+
+/// Give HTML a base, as a data URL has none of its own
+fn with_base(html: &[u8], base_url: &Url) -> Vec<u8> {
+    let base = format!("<base href=\"{}\">", escape_attribute(base_url.as_str()));
+    // Keep the doctype first, otherwise the page is in quirks mode
+    let start = html
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(html.len());
+    let doctype_end = html[start..]
+        .get(..9)
+        .filter(|start| start.eq_ignore_ascii_case(b"<!doctype"))
+        .and_then(|_| html.iter().position(|byte| *byte == b'>'))
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    [&html[..doctype_end], base.as_bytes(), &html[doctype_end..]].concat()
+}
+
+/// Content which is loaded directly instead of from a URL
+pub(crate) struct QServoContent {
+    pub(crate) data_url: ServoUrl,
+    /// The URL which is reported instead of the data URL
+    pub(crate) base_url: Url,
+}
+
+impl QServoContent {
+    /// HTML where relative URLs are resolved against the base URL
+    pub(crate) fn html(html: &str, base_url: Option<Url>) -> Result<Self, String> {
+        Self::data(html.as_bytes(), "text/html;charset=utf-8", base_url)
+    }
+
+    /// Data of the MIME type, relative URLs of HTML are resolved against the base URL
+    pub(crate) fn data(
+        data: &[u8],
+        mime_type: &str,
+        base_url: Option<Url>,
+    ) -> Result<Self, String> {
+        let is_html = mime_type
+            .split(';')
+            .next()
+            .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("text/html"));
+        let data_url = match base_url.as_ref() {
+            Some(base_url) if is_html => to_data_url(mime_type, &with_base(data, base_url))?,
+            _ => to_data_url(mime_type, data)?,
+        };
+
+        Ok(Self {
+            data_url,
+            base_url: base_url.unwrap_or_else(|| Url::parse("about:blank").unwrap()),
+        })
+    }
+}
+
+fn escape_attribute(text: &str) -> String {
+    text.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_url() -> Url {
+        Url::parse("https://example.com/help/").unwrap()
+    }
+
+    fn html_with_base(html: &str) -> String {
+        String::from_utf8(with_base(html.as_bytes(), &base_url())).unwrap()
+    }
+
+    #[test]
+    fn html_base_without_doctype() {
+        assert_eq!(
+            html_with_base("<p>hello</p>"),
+            "<base href=\"https://example.com/help/\"><p>hello</p>"
+        );
+    }
+
+    #[test]
+    fn html_base_after_doctype() {
+        assert_eq!(
+            html_with_base("<!DOCTYPE html><p>hello</p>"),
+            "<!DOCTYPE html><base href=\"https://example.com/help/\"><p>hello</p>"
+        );
+    }
+
+    #[test]
+    fn html_base_after_doctype_with_whitespace() {
+        assert_eq!(
+            html_with_base("\n  <!doctype html>\n<p>hello</p>"),
+            "\n  <!doctype html><base href=\"https://example.com/help/\">\n<p>hello</p>"
+        );
+    }
+
+    #[test]
+    fn html_base_escaped() {
+        let base_url = Url::parse("https://example.com/?a=1&b=\"2\"").unwrap();
+        let html = String::from_utf8(with_base(b"<p>hello</p>", &base_url)).unwrap();
+        assert_eq!(
+            html,
+            "<base href=\"https://example.com/?a=1&amp;b=%222%22\"><p>hello</p>"
+        );
+    }
+
+    #[test]
+    fn html_content() {
+        let content = QServoContent::html("<!DOCTYPE html><p>hello</p>", Some(base_url())).unwrap();
+        let expected = to_data_url(
+            "text/html;charset=utf-8",
+            b"<!DOCTYPE html><base href=\"https://example.com/help/\"><p>hello</p>",
+        )
+        .unwrap();
+        assert_eq!(content.data_url, expected);
+        assert_eq!(content.base_url, base_url());
+    }
+
+    #[test]
+    fn html_content_without_base_url() {
+        let content = QServoContent::html("<p>hello</p>", None).unwrap();
+        let expected = to_data_url("text/html;charset=utf-8", b"<p>hello</p>").unwrap();
+        assert_eq!(content.data_url, expected);
+        assert_eq!(content.base_url.as_str(), "about:blank");
+    }
+
+    #[test]
+    fn data_content_html_has_base() {
+        let content = QServoContent::data(b"<p>hello</p>", "Text/HTML", Some(base_url())).unwrap();
+        let expected = to_data_url(
+            "Text/HTML",
+            b"<base href=\"https://example.com/help/\"><p>hello</p>",
+        )
+        .unwrap();
+        assert_eq!(content.data_url, expected);
+    }
+
+    #[test]
+    fn data_content_other_has_no_base() {
+        let content = QServoContent::data(b"hello", "text/plain", Some(base_url())).unwrap();
+        assert_eq!(content.data_url, to_data_url("text/plain", b"hello").unwrap());
+    }
+
+    #[test]
+    fn data_url_invalid_mime_type() {
+        assert!(to_data_url("text/html,<script>", b"").is_err());
+        assert!(to_data_url("text/html#fragment", b"").is_err());
+        assert!(to_data_url("text/html\n", b"").is_err());
+    }
+
+    #[test]
+    fn data_url_encoding() {
+        let data_url = to_data_url("text/plain;charset=utf-8", "a b,ü".as_bytes()).unwrap();
+        assert_eq!(data_url.as_str(), "data:text/plain;charset=utf-8,a%20b%2C%C3%BC");
+    }
+}
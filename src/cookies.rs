@@ -0,0 +1,528 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cookie::Cookie;
+use hyper_serde::Serde;
+use serde_json::{json, Value};
+use servo::{
+    compositing::windowing::EmbedderEvent,
+    ipc_channel::{ipc, router::ROUTER},
+    msg::constellation_msg::BrowsingContextId,
+    script_traits::{
+        webdriver_msg::WebDriverCookieError, WebDriverCommandMsg, WebDriverScriptCommand,
+    },
+    TopLevelBrowsingContextId as WebViewId,
+};
+use url::Url;
+
+/// A cookie of the cookie store
+#[derive(Clone, Debug, PartialEq)]
+pub struct QServoCookie {
+    pub name: String,
+    pub value: String,
+    /// The host the cookie is for, with a leading dot it is for subdomains too,
+    /// otherwise it is a host-only cookie
+    pub domain: String,
+    pub path: String,
+    /// A session cookie has no expiry
+    pub expires: Option<SystemTime>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+/// Check that an attribute cannot end the cookie early or add attributes of its own
+fn check_attribute(cookie: &str, kind: &str, value: &str) -> Result<(), String> {
+    if value.chars().any(|c| c.is_control() || c == ';') {
+        Err(format!("invalid {kind} of cookie {cookie}"))
+    } else {
+        Ok(())
+    }
+}
+
+impl QServoCookie {
+    /// A cookie given to the page of the host
+    ///
+    /// TODO: Servo does not tell us whether a cookie with the host of the page as its
+    /// domain is host-only, so such a cookie is assumed to be. A cookie of another
+    /// domain has only been given to the page because it is for subdomains too.
+    fn from_servo(cookie: &Cookie, page_host: &str) -> Self {
+        // The expiry is only available as the Set-Cookie attribute in every version
+        // of the cookie crate, so read it back from there
+        let mut expires = None;
+        for attribute in cookie.to_string().split(';').skip(1) {
+            let (name, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            match name.trim().to_ascii_lowercase().as_str() {
+                "expires" => {
+                    expires = expires.or(httpdate::parse_http_date(value.trim()).ok());
+                }
+                "max-age" => {
+                    if let Ok(max_age) = value.trim().parse::<i64>() {
+                        expires = Some(if max_age > 0 {
+                            SystemTime::now() + Duration::from_secs(max_age as u64)
+                        } else {
+                            UNIX_EPOCH
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            domain: match cookie.domain() {
+                Some(domain) if domain.starts_with('.') => domain.to_owned(),
+                Some(domain) if !domain.eq_ignore_ascii_case(page_host) => format!(".{domain}"),
+                Some(domain) => domain.to_owned(),
+                None => page_host.to_owned(),
+            },
+            path: cookie.path().unwrap_or("/").to_owned(),
+            expires,
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+        }
+    }
+
+    fn to_servo(&self) -> Result<Cookie<'static>, String> {
+        if self.name.is_empty() || self.name.contains(|c: char| c == '=' || c.is_whitespace()) {
+            return Err(format!("invalid cookie name {:?}", self.name));
+        }
+        check_attribute(&self.name, "name", &self.name)?;
+        check_attribute(&self.name, "value", &self.value)?;
+        check_attribute(&self.name, "domain", &self.domain)?;
+        check_attribute(&self.name, "path", &self.path)?;
+
+        let mut builder = Cookie::build(self.name.clone(), self.value.clone())
+            .path(self.path.clone())
+            .secure(self.secure)
+            .http_only(self.http_only);
+        // Without a domain Servo makes a host-only cookie for the host of the page
+        if let Some(domain) = self.domain.strip_prefix('.') {
+            builder = builder.domain(domain.to_owned());
+        }
+        let mut cookie = builder.finish();
+
+        if let Some(expires) = self.expires {
+            // The cookie crate only takes the expiry as a time of the old time crate, so
+            // read it from an attribute which we wrote ourselves
+            let attribute = format!("expiry=; Expires={}", httpdate::fmt_http_date(expires));
+            let expires = Cookie::parse(attribute)
+                .ok()
+                .and_then(|cookie| cookie.expires())
+                .ok_or_else(|| format!("invalid expiry of cookie {}", self.name))?;
+            cookie.set_expires(expires);
+        }
+
+        Ok(cookie)
+    }
+
+    /// Check that Servo can set the cookie for the page of the URL, which is only the
+    /// case without a domain or with the host of the page as the domain
+    fn for_page(&self, page_url: &Url) -> Result<(), String> {
+        let host = page_url.host_str().unwrap_or_default();
+        let domain = self.domain.trim_start_matches('.');
+        if domain.is_empty() || domain.eq_ignore_ascii_case(host) {
+            Ok(())
+        } else {
+            Err(format!(
+                "cookie {} is for {domain} but the page is of {host}",
+                self.name
+            ))
+        }
+    }
+
+    /// The same cookie with an expiry in the past, setting this removes the cookie
+    pub(crate) fn expired(&self) -> Self {
+        Self {
+            expires: Some(UNIX_EPOCH),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the cookie is sent to the host, an empty host matches every cookie
+    ///
+    /// A host-only cookie is only sent to its own host, not to subdomains.
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        let domain = self.domain.to_ascii_lowercase();
+        if host.is_empty() || domain.is_empty() {
+            return true;
+        }
+
+        match domain.strip_prefix('.') {
+            Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+            None => host == domain,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "value": self.value,
+            "domain": self.domain,
+            "path": self.path,
+            "expires": self.expires.and_then(|expires| {
+                expires.duration_since(UNIX_EPOCH).ok().map(|expires| expires.as_millis() as u64)
+            }),
+            "secure": self.secure,
+            "httpOnly": self.http_only,
+        })
+    }
+
+    /// A cookie from a QML object, the expiry is in milliseconds since the epoch
+    pub(crate) fn from_json(value: &Value) -> Option<Self> {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+        let boolean = |key: &str| value.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+        Some(Self {
+            name: string("name").filter(|name| !name.is_empty())?,
+            value: string("value").unwrap_or_default(),
+            domain: string("domain").unwrap_or_default(),
+            path: string("path").unwrap_or_else(|| "/".to_owned()),
+            expires: value
+                .get("expires")
+                .and_then(Value::as_f64)
+                .map(|expires| UNIX_EPOCH + Duration::from_millis(expires.max(0.0) as u64)),
+            secure: boolean("secure"),
+            http_only: boolean("httpOnly"),
+        })
+    }
+}
+
+/// Write cookies in the Netscape cookie file format, which curl and browsers read
+pub fn cookies_to_netscape(cookies: &[QServoCookie]) -> String {
+    let mut text = "# Netscape HTTP Cookie File\n".to_owned();
+    for cookie in cookies {
+        let expires = cookie
+            .expires
+            .and_then(|expires| expires.duration_since(UNIX_EPOCH).ok())
+            .map(|expires| expires.as_secs())
+            .unwrap_or(0);
+        let bool_field = |value: bool| if value { "TRUE" } else { "FALSE" };
+
+        text.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{expires}\t{}\t{}\n",
+            if cookie.http_only { "#HttpOnly_" } else { "" },
+            cookie.domain,
+            bool_field(cookie.domain.starts_with('.')),
+            cookie.path,
+            bool_field(cookie.secure),
+            cookie.name,
+            cookie.value,
+        ));
+    }
+    text
+}
+
+/// Read cookies from the Netscape cookie file format, invalid lines are skipped
+pub fn cookies_from_netscape(text: &str) -> Vec<QServoCookie> {
+    text.lines()
+        .filter_map(|line| {
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => (line, true),
+                None if line.starts_with('#') => return None,
+                None => (line, false),
+            };
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [domain, subdomains, path, secure, expires, name, value] = fields[..] else {
+                return None;
+            };
+
+            let domain = match (subdomains, domain.starts_with('.')) {
+                ("TRUE", false) => format!(".{domain}"),
+                _ => domain.to_owned(),
+            };
+            let expires = expires
+                .parse::<u64>()
+                .ok()
+                .filter(|expires| *expires > 0)
+                .map(|expires| UNIX_EPOCH + Duration::from_secs(expires));
+
+            Some(QServoCookie {
+                name: name.to_owned(),
+                value: value.to_owned(),
+                domain,
+                path: path.to_owned(),
+                expires,
+                secure: secure == "TRUE",
+                http_only,
+            })
+        })
+        .collect()
+}
+
+type QServoCookiesCallback = Box<dyn FnOnce(Vec<QServoCookie>) + Send>;
+type QServoCookieSetCallback = Box<dyn FnOnce(Result<(), String>) + Send>;
+
+/// A request to the cookie store of Servo
+///
+/// TODO: Servo only gives embedders the cookies of the page through WebDriver, so
+/// only the cookies which are sent to the current page can be read, and cookies can
+/// only be set for the host of the current page. So there needs to be a page before
+/// a cookie can be set, and cookies cannot be set before the first load, for example for
+/// single sign-on. There is also no notification when a page changes its cookies.
+pub(crate) enum QServoCookieRequest {
+    List(QServoCookiesCallback),
+    Set(QServoCookie, QServoCookieSetCallback),
+}
+
+impl QServoCookieRequest {
+    /// Build the event for the request to the page of the URL, the callback is called
+    /// on Servo's IPC router thread
+    ///
+    /// When there is an error the callback has been answered already.
+    pub(crate) fn into_event(
+        self,
+        webview_id: WebViewId,
+        page_url: &Url,
+    ) -> Result<EmbedderEvent, String> {
+        let command = match self {
+            Self::List(callback) => {
+                let (sender, receiver) = match ipc::channel() {
+                    Ok(channel) => channel,
+                    Err(err) => {
+                        callback(vec![]);
+                        return Err(format!("{err:?}"));
+                    }
+                };
+
+                // The router wants a FnMut but there is only one answer
+                let mut callback = Some(callback);
+                let page_host = page_url.host_str().unwrap_or_default().to_owned();
+                ROUTER.add_route(
+                    receiver.to_opaque(),
+                    Box::new(move |message| {
+                        let page_host = page_host.as_str();
+                        let cookies = message
+                            .to::<Vec<Serde<Cookie<'static>>>>()
+                            .map(|cookies| {
+                                cookies
+                                    .into_iter()
+                                    .map(|cookie| QServoCookie::from_servo(&cookie.0, page_host))
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        if let Some(callback) = callback.take() {
+                            callback(cookies);
+                        }
+                    }),
+                );
+                WebDriverScriptCommand::GetCookies(sender)
+            }
+            Self::Set(cookie, callback) => {
+                let channel = cookie
+                    .for_page(page_url)
+                    .and_then(|_| cookie.to_servo())
+                    .and_then(|servo_cookie| {
+                        let (sender, receiver) =
+                            ipc::channel().map_err(|err| format!("{err:?}"))?;
+                        Ok((servo_cookie, sender, receiver))
+                    });
+                let (servo_cookie, sender, receiver) = match channel {
+                    Ok(channel) => channel,
+                    Err(err) => {
+                        callback(Err(err.clone()));
+                        return Err(err);
+                    }
+                };
+
+                let mut callback = Some(callback);
+                ROUTER.add_route(
+                    receiver.to_opaque(),
+                    Box::new(move |message| {
+                        let result = match message.to::<Result<(), WebDriverCookieError>>() {
+                            Ok(Ok(())) => Ok(()),
+                            Ok(Err(err)) => {
+                                Err(format!("failed to set cookie {}: {err:?}", cookie.name))
+                            }
+                            Err(err) => Err(format!("{err:?}")),
+                        };
+                        if let Some(callback) = callback.take() {
+                            callback(result);
+                        }
+                    }),
+                );
+                WebDriverScriptCommand::AddCookie(servo_cookie, sender)
+            }
+        };
+
+        Ok(EmbedderEvent::WebDriverCommand(
+            WebDriverCommandMsg::ScriptCommand(BrowsingContextId::from(webview_id), command),
+        ))
+    }
+
+    /// Answer the request without Servo, for when there is no page
+    pub(crate) fn fail(self, err: &str) {
+        match self {
+            Self::List(callback) => callback(vec![]),
+            Self::Set(_, callback) => callback(Err(err.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(name: &str, domain: &str) -> QServoCookie {
+        QServoCookie {
+            name: name.to_owned(),
+            value: "value".to_owned(),
+            domain: domain.to_owned(),
+            path: "/".to_owned(),
+            expires: None,
+            secure: false,
+            http_only: false,
+        }
+    }
+
+    #[test]
+    fn netscape_round_trip() {
+        let cookies = vec![
+            cookie("session", "example.com"),
+            QServoCookie {
+                expires: Some(UNIX_EPOCH + Duration::from_secs(2_000_000_000)),
+                secure: true,
+                http_only: true,
+                path: "/app".to_owned(),
+                ..cookie("token", ".example.com")
+            },
+        ];
+        assert_eq!(cookies_from_netscape(&cookies_to_netscape(&cookies)), cookies);
+    }
+
+    #[test]
+    fn netscape_format() {
+        let text = cookies_to_netscape(&[QServoCookie {
+            http_only: true,
+            ..cookie("name", ".example.com")
+        }]);
+        assert_eq!(
+            text,
+            "# Netscape HTTP Cookie File\n\
+             #HttpOnly_.example.com\tTRUE\t/\tFALSE\t0\tname\tvalue\n"
+        );
+    }
+
+    #[test]
+    fn netscape_subdomains_add_dot() {
+        let cookies = cookies_from_netscape("example.com\tTRUE\t/\tFALSE\t0\tname\tvalue");
+        assert_eq!(cookies, vec![cookie("name", ".example.com")]);
+    }
+
+    #[test]
+    fn netscape_session_cookie() {
+        let cookies = cookies_from_netscape("example.com\tFALSE\t/\tFALSE\t0\tname\tvalue");
+        assert_eq!(cookies[0].expires, None);
+    }
+
+    #[test]
+    fn netscape_skips_invalid_lines() {
+        let text = "# Netscape HTTP Cookie File\n\
+                    \n\
+                    # a comment\n\
+                    example.com\tFALSE\t/\n\
+                    example.com\tFALSE\t/\tFALSE\t0\tname\tvalue\n";
+        assert_eq!(cookies_from_netscape(text), vec![cookie("name", "example.com")]);
+    }
+
+    #[test]
+    fn to_servo() {
+        let servo_cookie = QServoCookie {
+            secure: true,
+            http_only: true,
+            ..cookie("name", ".example.com")
+        }
+        .to_servo()
+        .unwrap();
+        assert_eq!(servo_cookie.name(), "name");
+        assert_eq!(servo_cookie.value(), "value");
+        assert_eq!(servo_cookie.domain(), Some("example.com"));
+        assert_eq!(servo_cookie.path(), Some("/"));
+        assert_eq!(servo_cookie.secure(), Some(true));
+        assert_eq!(servo_cookie.http_only(), Some(true));
+        assert!(servo_cookie.expires().is_none());
+    }
+
+    #[test]
+    fn to_servo_host_only() {
+        let servo_cookie = cookie("name", "example.com").to_servo().unwrap();
+        assert_eq!(servo_cookie.domain(), None);
+    }
+
+    #[test]
+    fn to_servo_expires() {
+        let servo_cookie = QServoCookie {
+            expires: Some(UNIX_EPOCH + Duration::from_secs(2_000_000_000)),
+            ..cookie("name", "example.com")
+        }
+        .to_servo()
+        .unwrap();
+        assert_eq!(
+            QServoCookie::from_servo(&servo_cookie, "example.com").expires,
+            Some(UNIX_EPOCH + Duration::from_secs(2_000_000_000))
+        );
+    }
+
+    #[test]
+    fn to_servo_refuses_injection() {
+        let invalid = [
+            QServoCookie {
+                value: "value; Domain=evil.com".to_owned(),
+                ..cookie("name", "")
+            },
+            QServoCookie {
+                path: "/; HttpOnly".to_owned(),
+                ..cookie("name", "")
+            },
+            QServoCookie {
+                value: "value\nExpires=0".to_owned(),
+                ..cookie("name", "")
+            },
+            cookie("name=other", ""),
+            cookie("", ""),
+        ];
+        for cookie in invalid {
+            assert!(cookie.to_servo().is_err(), "{cookie:?}");
+        }
+    }
+
+    #[test]
+    fn from_servo_domain() {
+        let servo_cookie = Cookie::build("name", "value").domain("example.com").finish();
+        assert_eq!(
+            QServoCookie::from_servo(&servo_cookie, "www.example.com").domain,
+            ".example.com"
+        );
+        assert_eq!(QServoCookie::from_servo(&servo_cookie, "example.com").domain, "example.com");
+    }
+
+    #[test]
+    fn matches_host() {
+        let host_only = cookie("name", "example.com");
+        assert!(host_only.matches_host(""));
+        assert!(host_only.matches_host("EXAMPLE.com"));
+        assert!(!host_only.matches_host("www.example.com"));
+
+        let subdomains = cookie("name", ".example.com");
+        assert!(subdomains.matches_host("example.com"));
+        assert!(subdomains.matches_host("www.example.com"));
+        assert!(!subdomains.matches_host("badexample.com"));
+    }
+
+    #[test]
+    fn for_page() {
+        let page = Url::parse("https://www.example.com/path").unwrap();
+        assert!(cookie("name", "").for_page(&page).is_ok());
+        assert!(cookie("name", "www.example.com").for_page(&page).is_ok());
+        assert!(cookie("name", ".WWW.example.com").for_page(&page).is_ok());
+        assert!(cookie("name", "example.com").for_page(&page).is_err());
+        assert!(cookie("name", "other.com").for_page(&page).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::sync::Arc;
+
+use servo::servo_url::ServoUrl;
+use url::Url;
+
+use crate::{content::to_data_url, webview::qobject::ErrorDomain};
+
+/// A callback which returns the HTML to show for a failed load of the given URL
+pub type QServoErrorPageHandler = dyn Fn(&Url, ErrorDomain, &str) -> String + Send + Sync;
+
+/// The HTML which replaces Servo's default error page
+#[derive(Clone)]
+pub(crate) struct QServoErrorPage(Arc<QServoErrorPageHandler>);
+
+impl QServoErrorPage {
+    pub(crate) fn new(handler: Arc<QServoErrorPageHandler>) -> Self {
+        Self(handler)
+    }
+
+    /// Build a data URL of the error page for the given failed load
+    pub(crate) fn to_data_url(
+        &self,
+        url: &Url,
+        error_domain: ErrorDomain,
+        reason: &str,
+    ) -> Result<ServoUrl, String> {
+        let html = (self.0)(url, error_domain, reason);
+        to_data_url("text/html;charset=utf-8", html.as_bytes())
+    }
+}
+
+/// Fill a HTML template where `${url}` and `${reason}` are replaced
+pub(crate) fn fill_template(template: &str, url: &Url, reason: &str) -> String {
+    template
+        .replace("${url}", &escape_html(url.as_str()))
+        .replace("${reason}", &escape_html(reason))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,154 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servofileselectionrequest")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstringlist.h");
+        type QStringList = cxx_qt_lib::QStringList;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_file_selection_request_unique_ptr() -> UniquePtr<ServoFileSelectionRequest>;
+    }
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QStringList, accepted_filters)]
+        #[qproperty(bool, allow_multiple)]
+        type ServoFileSelectionRequest = super::QServoFileSelectionRequestRust;
+
+        /// Answer with local paths or file URLs
+        #[qinvokable]
+        fn accept(self: Pin<&mut ServoFileSelectionRequest>, files: &QStringList);
+
+        #[qinvokable]
+        fn cancel(self: Pin<&mut ServoFileSelectionRequest>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoFileSelectionRequest {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::{QList, QString, QStringList};
+use servo::{embedder_traits::FilterPattern, ipc_channel::ipc::IpcSender};
+use url::Url;
+
+/// Convert local paths or file URLs from Qt into the paths Servo expects
+pub(crate) fn paths_from_qstringlist(files: &QStringList) -> Vec<String> {
+    let files: &QList<QString> = files.as_ref();
+    files
+        .iter()
+        .map(String::from)
+        .filter(|file| !file.is_empty())
+        .map(|file| {
+            Url::parse(&file)
+                .ok()
+                .filter(|url| url.scheme() == "file")
+                .and_then(|url| url.to_file_path().ok())
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or(file)
+        })
+        .collect()
+}
+
+struct QServoFileSelectionResponder {
+    allow_multiple: bool,
+    sender: IpcSender<Option<Vec<String>>>,
+}
+
+impl QServoFileSelectionResponder {
+    fn respond(self, files: Option<Vec<String>>) {
+        let files = files.filter(|files| !files.is_empty()).map(|mut files| {
+            if !self.allow_multiple {
+                files.truncate(1);
+            }
+            files
+        });
+        if let Err(err) = self.sender.send(files) {
+            println!("failed to answer file selection request: {err:?}");
+        }
+    }
+}
+
+/// A file selection requested by the page which has not been shown to Qt yet
+pub(crate) struct QServoFileSelectionRequest {
+    accepted_filters: Vec<String>,
+    responder: QServoFileSelectionResponder,
+}
+
+impl QServoFileSelectionRequest {
+    pub(crate) fn new(
+        filters: Vec<FilterPattern>,
+        allow_multiple: bool,
+        sender: IpcSender<Option<Vec<String>>>,
+    ) -> Self {
+        Self {
+            accepted_filters: filters.into_iter().map(|filter| filter.0).collect(),
+            responder: QServoFileSelectionResponder {
+                allow_multiple,
+                sender,
+            },
+        }
+    }
+
+    pub(crate) fn respond(self, files: Option<Vec<String>>) {
+        self.responder.respond(files);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoFileSelectionRequest> {
+        let mut request = qobject::servo_file_selection_request_unique_ptr();
+        let mut accepted_filters = QList::<QString>::default();
+        for filter in self.accepted_filters.iter() {
+            accepted_filters.append(QString::from(filter));
+        }
+        request
+            .pin_mut()
+            .set_accepted_filters(QStringList::from(&accepted_filters));
+        request
+            .pin_mut()
+            .set_allow_multiple(self.responder.allow_multiple);
+        request.pin_mut().rust_mut().responder = Some(self.responder);
+        request
+    }
+}
+
+#[derive(Default)]
+pub struct QServoFileSelectionRequestRust {
+    accepted_filters: QStringList,
+    allow_multiple: bool,
+    responder: Option<QServoFileSelectionResponder>,
+}
+
+impl Drop for QServoFileSelectionRequestRust {
+    fn drop(&mut self) {
+        // Never leave the page waiting for an answer
+        if let Some(responder) = self.responder.take() {
+            responder.respond(None);
+        }
+    }
+}
+
+impl qobject::ServoFileSelectionRequest {
+    /// Whether the request has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.responder.is_none()
+    }
+
+    fn accept(mut self: Pin<&mut Self>, files: &QStringList) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(Some(paths_from_qstringlist(files)));
+        }
+    }
+
+    fn cancel(mut self: Pin<&mut Self>) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(None);
+        }
+    }
+}
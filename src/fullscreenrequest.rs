@@ -0,0 +1,118 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servofullscreenrequest")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qurl.h");
+        type QUrl = cxx_qt_lib::QUrl;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_full_screen_request_unique_ptr() -> UniquePtr<ServoFullScreenRequest>;
+    }
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QUrl, origin)]
+        #[qproperty(bool, toggle_on)]
+        type ServoFullScreenRequest = super::QServoFullScreenRequestRust;
+
+        /// Let the page enter or leave fullscreen, isFullScreen changes
+        #[qinvokable]
+        fn accept(self: Pin<&mut ServoFullScreenRequest>);
+
+        /// Keep the page out of fullscreen
+        ///
+        /// The page has already left when toggleOn is false, so this is the same as
+        /// accept.
+        #[qinvokable]
+        fn reject(self: Pin<&mut ServoFullScreenRequest>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoFullScreenRequest {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::QUrl;
+use url::Url;
+
+/// Whether the page may be in fullscreen, called once with the answer
+pub(crate) type QServoFullScreenResponder = Box<dyn FnOnce(bool)>;
+
+/// A page entering or leaving fullscreen which has not been shown to Qt yet
+pub(crate) struct QServoFullScreenRequest {
+    origin: Option<Url>,
+    toggle_on: bool,
+    responder: QServoFullScreenResponder,
+}
+
+impl QServoFullScreenRequest {
+    pub(crate) fn new(
+        origin: Option<Url>,
+        toggle_on: bool,
+        responder: QServoFullScreenResponder,
+    ) -> Self {
+        Self {
+            origin,
+            toggle_on,
+            responder,
+        }
+    }
+
+    /// Let the page have its way, used when there is no handler in Qt
+    pub(crate) fn accept(self) {
+        (self.responder)(self.toggle_on);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoFullScreenRequest> {
+        let mut request = qobject::servo_full_screen_request_unique_ptr();
+        if let Some(origin) = self.origin.as_ref() {
+            request.pin_mut().set_origin(QUrl::from(origin));
+        }
+        request.pin_mut().set_toggle_on(self.toggle_on);
+        request.pin_mut().rust_mut().responder = Some(self.responder);
+        request
+    }
+}
+
+#[derive(Default)]
+pub struct QServoFullScreenRequestRust {
+    origin: QUrl,
+    toggle_on: bool,
+    responder: Option<QServoFullScreenResponder>,
+}
+
+impl Drop for QServoFullScreenRequestRust {
+    fn drop(&mut self) {
+        // Never leave the page in fullscreen without the host knowing
+        if let Some(responder) = self.responder.take() {
+            responder(false);
+        }
+    }
+}
+
+impl qobject::ServoFullScreenRequest {
+    /// Whether the request has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.responder.is_none()
+    }
+
+    fn accept(mut self: Pin<&mut Self>) {
+        let toggle_on = *self.as_ref().toggle_on();
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder(toggle_on);
+        }
+    }
+
+    fn reject(mut self: Pin<&mut Self>) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder(false);
+        }
+    }
+}
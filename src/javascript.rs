@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use cxx_qt::CxxQtThread;
+use serde_json::{json, Map, Value};
+use servo::{
+    compositing::windowing::EmbedderEvent,
+    ipc_channel::{
+        ipc::{self, IpcSender},
+        router::ROUTER,
+    },
+    msg::constellation_msg::BrowsingContextId,
+    script_traits::{
+        webdriver_msg::{WebDriverJSResult, WebDriverJSValue},
+        WebDriverCommandMsg, WebDriverScriptCommand,
+    },
+    TopLevelBrowsingContextId as WebViewId,
+};
+
+use crate::webview::qobject::ServoWebView;
+
+/// Convert a JavaScript value from Servo into JSON, which Qt turns into a QVariant
+fn value_to_json(value: WebDriverJSValue) -> Value {
+    match value {
+        WebDriverJSValue::Undefined | WebDriverJSValue::Null => Value::Null,
+        WebDriverJSValue::Boolean(value) => Value::Bool(value),
+        WebDriverJSValue::Int(value) => json!(value),
+        WebDriverJSValue::Number(value) => json!(value),
+        WebDriverJSValue::String(value) => Value::String(value),
+        WebDriverJSValue::ArrayLike(values) => {
+            Value::Array(values.into_iter().map(value_to_json).collect())
+        }
+        WebDriverJSValue::Object(values) => Value::Object(
+            values
+                .into_iter()
+                .map(|(key, value)| (key, value_to_json(value)))
+                .collect::<Map<String, Value>>(),
+        ),
+        // Elements and windows only make sense to WebDriver, so give their reference
+        WebDriverJSValue::Element(element) => json!({ "element": element.0 }),
+        WebDriverJSValue::Frame(frame) => json!({ "frame": frame.0 }),
+        WebDriverJSValue::Window(window) => json!({ "window": window.0 }),
+    }
+}
+
+/// The result of a script as JSON, or the error message if it threw or could not run
+pub(crate) type QServoJavaScriptResult = Result<String, String>;
+
+fn result_to_json(result: WebDriverJSResult) -> QServoJavaScriptResult {
+    match result {
+        Ok(value) => Ok(value_to_json(value).to_string()),
+        // TODO: Servo does not give the message of an exception yet
+        Err(err) => Err(format!("{err:?}")),
+    }
+}
+
+/// Build the event which evaluates a script in the top level browsing context
+///
+/// The result is given to the callback, on Servo's IPC router thread.
+pub(crate) fn execute_script(
+    webview_id: WebViewId,
+    script: String,
+    callback: impl FnOnce(QServoJavaScriptResult) + Send + 'static,
+) -> Result<EmbedderEvent, String> {
+    script_command(webview_id, callback, |sender| {
+        WebDriverScriptCommand::ExecuteScript(script, sender)
+    })
+}
+
+/// Build the event which evaluates a script that finishes later
+///
+/// The script calls window.webdriverCallback with its result, which is given to the
+/// callback on Servo's IPC router thread.
+pub(crate) fn execute_async_script(
+    webview_id: WebViewId,
+    script: String,
+    callback: impl FnOnce(QServoJavaScriptResult) + Send + 'static,
+) -> Result<EmbedderEvent, String> {
+    script_command(webview_id, callback, |sender| {
+        WebDriverScriptCommand::ExecuteAsyncScript(script, sender)
+    })
+}
+
+fn script_command(
+    webview_id: WebViewId,
+    callback: impl FnOnce(QServoJavaScriptResult) + Send + 'static,
+    command: impl FnOnce(IpcSender<WebDriverJSResult>) -> WebDriverScriptCommand,
+) -> Result<EmbedderEvent, String> {
+    let (sender, receiver) = ipc::channel().map_err(|err| format!("{err:?}"))?;
+
+    // The router wants a FnMut but there is only one result
+    let mut callback = Some(callback);
+    ROUTER.add_route(
+        receiver.to_opaque(),
+        Box::new(move |message| {
+            let result = match message.to::<WebDriverJSResult>() {
+                Ok(result) => result_to_json(result),
+                Err(err) => Err(format!("{err:?}")),
+            };
+
+            if let Some(callback) = callback.take() {
+                callback(result);
+            }
+        }),
+    );
+
+    Ok(EmbedderEvent::WebDriverCommand(
+        WebDriverCommandMsg::ScriptCommand(BrowsingContextId::from(webview_id), command(sender)),
+    ))
+}
+
+/// Evaluate a script for runJavaScript, the result is given to the web view on the Qt thread
+pub(crate) fn run_javascript(
+    webview_id: WebViewId,
+    id: u64,
+    script: String,
+    qt_thread: CxxQtThread<ServoWebView>,
+) -> Result<EmbedderEvent, String> {
+    execute_script(webview_id, script, move |result| {
+        if qt_thread
+            .queue(move |webview| webview.javascript_result(id, result))
+            .is_err()
+        {
+            println!("failed to queue javascript result, the web view has gone");
+        }
+    })
+}
@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servojavascriptdialog")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_javascript_dialog_unique_ptr() -> UniquePtr<ServoJavaScriptDialog>;
+    }
+
+    /// The kind of dialog the page asked for
+    #[qenum(ServoJavaScriptDialog)]
+    enum DialogType {
+        /// window.alert(), only needs to be acknowledged.
+        Alert,
+        /// window.confirm(), accepting or rejecting is the answer.
+        Confirm,
+        /// window.prompt(), the accepted text is the answer.
+        Prompt,
+    }
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QString, default_text)]
+        #[qproperty(DialogType, dialog_type)]
+        #[qproperty(QString, message)]
+        type ServoJavaScriptDialog = super::QServoJavaScriptDialogRust;
+
+        /// Answer the dialog, the text is only used for prompts
+        #[qinvokable]
+        fn accept(self: Pin<&mut ServoJavaScriptDialog>, text: &QString);
+
+        #[qinvokable]
+        fn reject(self: Pin<&mut ServoJavaScriptDialog>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoJavaScriptDialog {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::QString;
+use qobject::DialogType;
+use servo::{
+    embedder_traits::{PromptDefinition, PromptResult},
+    ipc_channel::ipc::IpcSender,
+};
+
+/// The IPC channel which Servo is waiting on for the answer of a dialog
+pub(crate) enum QServoJavaScriptDialogResponder {
+    Alert(IpcSender<()>),
+    Confirm(IpcSender<PromptResult>),
+    Prompt(IpcSender<Option<String>>),
+}
+
+impl QServoJavaScriptDialogResponder {
+    fn respond(self, accepted: bool, text: Option<String>) {
+        let result = match self {
+            Self::Alert(sender) => sender.send(()),
+            Self::Confirm(sender) => sender.send(if accepted {
+                PromptResult::Primary
+            } else {
+                PromptResult::Secondary
+            }),
+            Self::Prompt(sender) => sender.send(text.filter(|_| accepted)),
+        };
+
+        if let Err(err) = result {
+            println!("failed to answer javascript dialog: {err:?}");
+        }
+    }
+}
+
+/// A dialog requested by the page which has not been shown to Qt yet
+pub(crate) struct QServoJavaScriptDialogRequest {
+    pub(crate) dialog_type: DialogType,
+    pub(crate) message: String,
+    pub(crate) default_text: String,
+    responder: QServoJavaScriptDialogResponder,
+}
+
+impl QServoJavaScriptDialogRequest {
+    /// Returns the prompt definition back if it is not a javascript dialog
+    pub(crate) fn from_prompt(definition: PromptDefinition) -> Result<Self, PromptDefinition> {
+        let (dialog_type, message, default_text, responder) = match definition {
+            PromptDefinition::Alert(message, sender) => (
+                DialogType::Alert,
+                message,
+                String::default(),
+                QServoJavaScriptDialogResponder::Alert(sender),
+            ),
+            PromptDefinition::OkCancel(message, sender)
+            | PromptDefinition::YesNo(message, sender) => (
+                DialogType::Confirm,
+                message,
+                String::default(),
+                QServoJavaScriptDialogResponder::Confirm(sender),
+            ),
+            PromptDefinition::Input(message, default_text, sender) => (
+                DialogType::Prompt,
+                message,
+                default_text,
+                QServoJavaScriptDialogResponder::Prompt(sender),
+            ),
+            others => return Err(others),
+        };
+
+        Ok(Self {
+            dialog_type,
+            message,
+            default_text,
+            responder,
+        })
+    }
+
+    /// Answer as if the user closed the dialog, used when there is no handler in Qt
+    pub(crate) fn dismiss(self) {
+        self.responder.respond(false, None);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoJavaScriptDialog> {
+        let mut dialog = qobject::servo_javascript_dialog_unique_ptr();
+        dialog.pin_mut().set_dialog_type(self.dialog_type);
+        dialog.pin_mut().set_message(QString::from(&self.message));
+        dialog
+            .pin_mut()
+            .set_default_text(QString::from(&self.default_text));
+        dialog.pin_mut().rust_mut().responder = Some(self.responder);
+        dialog
+    }
+}
+
+pub struct QServoJavaScriptDialogRust {
+    default_text: QString,
+    dialog_type: DialogType,
+    message: QString,
+    responder: Option<QServoJavaScriptDialogResponder>,
+}
+
+impl Default for QServoJavaScriptDialogRust {
+    fn default() -> Self {
+        Self {
+            default_text: QString::default(),
+            dialog_type: DialogType::Alert,
+            message: QString::default(),
+            responder: None,
+        }
+    }
+}
+
+impl Drop for QServoJavaScriptDialogRust {
+    fn drop(&mut self) {
+        // Never leave the page waiting for an answer
+        if let Some(responder) = self.responder.take() {
+            responder.respond(false, None);
+        }
+    }
+}
+
+impl qobject::ServoJavaScriptDialog {
+    /// Whether the dialog has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.responder.is_none()
+    }
+
+    fn accept(mut self: Pin<&mut Self>, text: &QString) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(true, Some(String::from(text)));
+        }
+    }
+
+    fn reject(mut self: Pin<&mut Self>) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(false, None);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use serde_json::Value;
+
+use crate::webview::qobject::ErrorDomain;
+
+const NET_ERROR_PREFIX: &str = "Could not load the requested page:";
+
+/// Script which describes the loaded document, so that a failed load can be found
+///
+/// TODO: Servo does not tell the embedder when a load fails, instead it replaces the
+/// document with resources/neterror.html or resources/badcert.html, so look for those.
+/// Only the text of those pages is given, for the reason of the failure. The status of
+/// the response is only known if Servo has responseStatus.
+pub(crate) const LOAD_OUTCOME_SCRIPT: &str = r#"(() => {
+    const navigation = performance.getEntriesByType("navigation")[0];
+    const netError = document.title === "Error loading page";
+    const certificateError = typeof window.bypass === "function"
+        && document.title.toLowerCase().includes("certificate");
+    return {
+        netError: netError,
+        certificateError: certificateError,
+        errorText: (netError || certificateError) && document.body ? document.body.innerText : "",
+        responseStatus: navigation && navigation.responseStatus ? navigation.responseStatus : 0,
+    };
+})()"#;
+
+/// Script which counts the resources of the document which have finished loading
+pub(crate) const LOAD_PROGRESS_SCRIPT: &str = r#"performance.getEntriesByType("resource").length"#;
+
+/// How a load ended, from the description of LOAD_OUTCOME_SCRIPT
+#[derive(Default)]
+pub(crate) struct QServoLoadOutcome {
+    /// Why the load failed, if Servo showed one of its error pages
+    pub(crate) failure: Option<(ErrorDomain, String)>,
+    /// The HTTP status of the document, or 0 if it is not known
+    ///
+    /// Pages of an error status are the pages of the site, so they are not failures.
+    pub(crate) http_status_code: u16,
+}
+
+impl QServoLoadOutcome {
+    pub(crate) fn from_description(description: &Value) -> Self {
+        let flag = |name: &str| description.get(name).and_then(Value::as_bool) == Some(true);
+        let text = description
+            .get("errorText")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim();
+
+        let failure = if flag("netError") {
+            let reason = text.strip_prefix(NET_ERROR_PREFIX).unwrap_or(text);
+            Some((ErrorDomain::ConnectionErrorDomain, reason.trim().to_owned()))
+        } else if flag("certificateError") {
+            let reason = text.lines().next().unwrap_or_default().trim();
+            Some((ErrorDomain::CertificateErrorDomain, reason.to_owned()))
+        } else {
+            None
+        };
+
+        Self {
+            failure,
+            http_status_code: description
+                .get("responseStatus")
+                .and_then(Value::as_u64)
+                .and_then(|status| u16::try_from(status).ok())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// The progress of a load, from the stage it has reached and how many of its
+/// resources have finished
+///
+/// Servo has no progress of its own, so this is 10 once the load has started, at least
+/// 50 once the head has been parsed and moves towards 95 as resources finish. It never
+/// goes backwards during a load.
+#[derive(Default)]
+pub(crate) struct QServoLoadProgress {
+    head_parsed: bool,
+    requested: usize,
+    finished: usize,
+    value: i32,
+}
+
+impl QServoLoadProgress {
+    pub(crate) fn start(&mut self) -> i32 {
+        *self = Self::default();
+        self.update()
+    }
+
+    pub(crate) fn head_parsed(&mut self) -> i32 {
+        self.head_parsed = true;
+        self.update()
+    }
+
+    pub(crate) fn resource_requested(&mut self) {
+        self.requested += 1;
+    }
+
+    pub(crate) fn resources_finished(&mut self, finished: usize) -> i32 {
+        self.finished = self.finished.max(finished);
+        self.update()
+    }
+
+    /// Whether there are resources which have not finished yet
+    pub(crate) fn waiting_for_resources(&self) -> bool {
+        self.finished < self.requested
+    }
+
+    fn update(&mut self) -> i32 {
+        let stage = if self.head_parsed { 50 } else { 10 };
+        let resources = if self.requested == 0 {
+            0
+        } else {
+            10 + 85 * self.finished.min(self.requested) / self.requested
+        };
+        self.value = self.value.max(stage).max(resources as i32);
+        self.value
+    }
+}
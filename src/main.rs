@@ -5,11 +5,30 @@
 
 use cxx_qt_lib::{QGuiApplication, QQmlApplicationEngine, QUrl};
 
+mod audio;
+mod authenticationrequest;
+mod beforeunloadrequest;
 mod browser;
+mod browsingdata;
+mod content;
+mod cookies;
 mod embedder;
+mod errorpage;
 mod events_loop;
+mod fileselectionrequest;
+mod fullscreenrequest;
+mod javascript;
+mod javascriptdialog;
+mod loadstatus;
+mod mediasession;
+mod messagechannel;
+mod permissionrequest;
+mod profile;
 mod renderer;
 mod servothread;
+mod settings;
+mod urlscheme;
+mod webresourcerequest;
 mod webview;
 mod windowheadless;
 
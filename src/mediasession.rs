@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servomediasession")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_media_session_unique_ptr() -> UniquePtr<ServoMediaSession>;
+    }
+
+    /// The playback state of the media session of the page
+    #[qenum(ServoMediaSession)]
+    enum PlaybackState {
+        /// There is no active media session.
+        NoSession,
+        Playing,
+        Paused,
+    }
+
+    unsafe extern "RustQt" {
+        /// The media session of the page
+        ///
+        /// Servo does not support artwork in the media metadata, so there is none.
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QString, album)]
+        #[qproperty(QString, artist)]
+        #[qproperty(f64, duration)]
+        #[qproperty(f64, playback_rate)]
+        #[qproperty(PlaybackState, playback_state)]
+        #[qproperty(f64, position)]
+        #[qproperty(QString, title)]
+        type ServoMediaSession = super::QServoMediaSessionRust;
+
+        #[qinvokable]
+        fn pause(self: Pin<&mut ServoMediaSession>);
+
+        #[qinvokable]
+        fn play(self: Pin<&mut ServoMediaSession>);
+
+        /// Seek to the given position in seconds
+        ///
+        /// This seeks the audio or video element which is playing in the page.
+        #[qinvokable]
+        fn seek_to(self: Pin<&mut ServoMediaSession>, position: f64);
+
+        #[qinvokable]
+        fn stop(self: Pin<&mut ServoMediaSession>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoMediaSession {}
+}
+
+use core::pin::Pin;
+use cxx_qt::{CxxQtThread, CxxQtType};
+use cxx_qt_lib::QString;
+use qobject::PlaybackState;
+use servo::{
+    compositing::windowing::EmbedderEvent,
+    embedder_traits::{MediaSessionActionType, MediaSessionEvent, MediaSessionPlaybackState},
+};
+
+use crate::webview::qobject::ServoWebView;
+
+/// Script which seeks the media element which is playing, or else the first one
+fn seek_to_script(position: f64) -> String {
+    format!(
+        r#"(() => {{
+    const media = Array.from(document.querySelectorAll("audio, video"));
+    const element = media.find((element) => !element.paused) || media[0];
+    if (element) {{
+        element.currentTime = {position};
+    }}
+}})()"#
+    )
+}
+
+pub struct QServoMediaSessionRust {
+    album: QString,
+    artist: QString,
+    duration: f64,
+    playback_rate: f64,
+    playback_state: PlaybackState,
+    position: f64,
+    title: QString,
+    qt_thread: Option<CxxQtThread<ServoWebView>>,
+}
+
+impl Default for QServoMediaSessionRust {
+    fn default() -> Self {
+        Self {
+            album: QString::default(),
+            artist: QString::default(),
+            duration: 0.0,
+            playback_rate: 1.0,
+            playback_state: PlaybackState::NoSession,
+            position: 0.0,
+            title: QString::default(),
+            qt_thread: None,
+        }
+    }
+}
+
+impl qobject::ServoMediaSession {
+    /// Create the media session of a web view, actions are sent through the web view
+    pub(crate) fn new(qt_thread: CxxQtThread<ServoWebView>) -> cxx::UniquePtr<Self> {
+        let mut session = qobject::servo_media_session_unique_ptr();
+        session.pin_mut().rust_mut().qt_thread = Some(qt_thread);
+        session
+    }
+
+    /// Update the properties from a media session event of the page
+    pub(crate) fn handle_event(mut self: Pin<&mut Self>, event: MediaSessionEvent) {
+        match event {
+            MediaSessionEvent::SetMetadata(metadata) => {
+                self.as_mut().set_album(QString::from(&metadata.album));
+                self.as_mut().set_artist(QString::from(&metadata.artist));
+                self.as_mut().set_title(QString::from(&metadata.title));
+            }
+            MediaSessionEvent::PlaybackStateChange(state) => {
+                self.as_mut().set_playback_state(match state {
+                    MediaSessionPlaybackState::NoSession_ => PlaybackState::NoSession,
+                    MediaSessionPlaybackState::Playing => PlaybackState::Playing,
+                    MediaSessionPlaybackState::Paused => PlaybackState::Paused,
+                });
+            }
+            MediaSessionEvent::SetPositionState(position_state) => {
+                self.as_mut().set_duration(position_state.duration);
+                self.as_mut()
+                    .set_playback_rate(position_state.playback_rate);
+                self.as_mut().set_position(position_state.position);
+            }
+        }
+    }
+
+    /// Forget the media session, for example when the web view has closed
+    pub(crate) fn reset(mut self: Pin<&mut Self>) {
+        self.as_mut().set_album(QString::default());
+        self.as_mut().set_artist(QString::default());
+        self.as_mut().set_duration(0.0);
+        self.as_mut().set_playback_rate(1.0);
+        self.as_mut().set_playback_state(PlaybackState::NoSession);
+        self.as_mut().set_position(0.0);
+        self.as_mut().set_title(QString::default());
+    }
+
+    fn send_action(&self, action: MediaSessionActionType) {
+        if let Some(qt_thread) = self.qt_thread.as_ref() {
+            let queued = qt_thread.queue(move |mut webview| {
+                webview
+                    .as_mut()
+                    .rust_mut()
+                    .events
+                    .push(EmbedderEvent::MediaSessionAction(action));
+                webview.as_mut().update();
+            });
+            if queued.is_err() {
+                println!("failed to queue media session action, the web view has gone");
+            }
+        }
+    }
+
+    fn pause(self: Pin<&mut Self>) {
+        self.send_action(MediaSessionActionType::Pause);
+    }
+
+    fn play(self: Pin<&mut Self>) {
+        self.send_action(MediaSessionActionType::Play);
+    }
+
+    fn seek_to(self: Pin<&mut Self>, position: f64) {
+        if !position.is_finite() || position < 0.0 {
+            return;
+        }
+
+        // Servo does not pass a seek time with the seekto action, so seek the media
+        // element instead, the page then updates the position state
+        let script = seek_to_script(position);
+        if let Some(qt_thread) = self.qt_thread.as_ref() {
+            if qt_thread.queue(move |webview| webview.run_script(script)).is_err() {
+                println!("failed to queue seek, the web view has gone");
+            }
+        }
+    }
+
+    fn stop(self: Pin<&mut Self>) {
+        self.send_action(MediaSessionActionType::Stop);
+    }
+}
@@ -0,0 +1,143 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use url::Url;
+
+/// The prompt message which marks a prompt as a message for Qt
+///
+/// TODO: Servo has no way to expose a native object to the page yet, so
+/// window.qt.postMessage sends the message through a prompt which we answer
+const MESSAGE_PROMPT: &str = "com.kdab.servo.postMessage";
+
+/// A channel between the page and QML, which is only open to allowed origins
+///
+/// WARNING: Servo does not tell us which frame sent a message, so every message is
+/// attributed to the document in the top level frame. A frame of any origin inside an
+/// allowed page can send messages as that page, so only allow origins whose pages do
+/// not embed content which is not trusted. By default no origin is allowed.
+#[derive(Clone, Default)]
+pub(crate) struct QServoMessageChannel {
+    /// Serialised origins such as https://example.com, or * to allow any origin
+    allowed_origins: Vec<String>,
+}
+
+impl QServoMessageChannel {
+    pub(crate) fn new(allowed_origins: Vec<String>) -> Self {
+        Self { allowed_origins }
+    }
+
+    /// The origin of the URL if the channel is open to it
+    pub(crate) fn allowed_origin(&self, url: &Url) -> Option<String> {
+        // Custom schemes have an opaque origin, so use their scheme and host
+        let origin = url.origin();
+        let origin = if origin.is_tuple() {
+            origin.ascii_serialization()
+        } else {
+            format!("{}://{}", url.scheme(), url.host_str()?)
+        };
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || *allowed == origin)
+            .then_some(origin)
+    }
+
+    /// Script which adds window.qt to the page
+    ///
+    /// TODO: Servo cannot inject scripts before the scripts of the page run yet,
+    /// so pages need to wait for the load before using window.qt
+    pub(crate) fn injection_script() -> String {
+        format!(
+            r#"if (!window.qt) {{
+    window.qt = new EventTarget();
+    window.qt.onmessage = null;
+    window.qt.addEventListener("message", (event) => {{
+        if (typeof window.qt.onmessage === "function") {{
+            window.qt.onmessage(event);
+        }}
+    }});
+    window.qt.postMessage = (data) => {{
+        window.prompt("{MESSAGE_PROMPT}", JSON.stringify(data));
+    }};
+}}"#
+        )
+    }
+
+    /// Script which dispatches a message event with the JSON data at window.qt
+    pub(crate) fn post_message_script(json: &str) -> String {
+        format!(
+            r#"if (window.qt) {{
+    window.qt.dispatchEvent(new MessageEvent("message", {{ data: {json} }}));
+}}"#
+        )
+    }
+
+    /// Whether a prompt is a message from window.qt.postMessage
+    pub(crate) fn is_message_prompt(message: &str) -> bool {
+        message == MESSAGE_PROMPT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(allowed_origins: &[&str]) -> QServoMessageChannel {
+        QServoMessageChannel::new(allowed_origins.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn allowed_origin_empty() {
+        let channel = QServoMessageChannel::default();
+        assert_eq!(channel.allowed_origin(&url("https://example.com/")), None);
+    }
+
+    #[test]
+    fn allowed_origin_tuple() {
+        let channel = channel(&["https://example.com"]);
+        assert_eq!(
+            channel.allowed_origin(&url("https://example.com/path?query#fragment")),
+            Some("https://example.com".to_owned())
+        );
+        assert_eq!(channel.allowed_origin(&url("http://example.com/")), None);
+        assert_eq!(channel.allowed_origin(&url("https://example.com:8443/")), None);
+        assert_eq!(channel.allowed_origin(&url("https://sub.example.com/")), None);
+    }
+
+    #[test]
+    fn allowed_origin_port() {
+        let channel = channel(&["http://localhost:8080"]);
+        assert_eq!(
+            channel.allowed_origin(&url("http://localhost:8080/index.html")),
+            Some("http://localhost:8080".to_owned())
+        );
+        assert_eq!(channel.allowed_origin(&url("http://localhost/")), None);
+    }
+
+    #[test]
+    fn allowed_origin_custom_scheme() {
+        let channel = channel(&["app://ui"]);
+        assert_eq!(
+            channel.allowed_origin(&url("app://ui/index.html")),
+            Some("app://ui".to_owned())
+        );
+        assert_eq!(channel.allowed_origin(&url("app://other/index.html")), None);
+        assert_eq!(channel.allowed_origin(&url("data:text/html,hello")), None);
+    }
+
+    #[test]
+    fn allowed_origin_any() {
+        let channel = channel(&["*"]);
+        assert_eq!(
+            channel.allowed_origin(&url("https://example.com/")),
+            Some("https://example.com".to_owned())
+        );
+        // There is no origin to report for a URL without a host
+        assert_eq!(channel.allowed_origin(&url("about:blank")), None);
+    }
+}
@@ -0,0 +1,340 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servopermissionrequest")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("cxx-qt-lib/qurl.h");
+        type QUrl = cxx_qt_lib::QUrl;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_permission_request_unique_ptr() -> UniquePtr<ServoPermissionRequest>;
+    }
+
+    unsafe extern "RustQt" {
+        /// A feature asked for by the page of the origin
+        ///
+        /// The origin is of the page in the top level frame, Servo does not tell us which
+        /// frame asked, so a frame of another origin asks on behalf of the page and the
+        /// decision is remembered for the page.
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(QString, feature)]
+        #[qproperty(QUrl, origin)]
+        type ServoPermissionRequest = super::QServoPermissionRequestRust;
+
+        #[qinvokable]
+        fn grant(self: Pin<&mut ServoPermissionRequest>);
+
+        #[qinvokable]
+        fn deny(self: Pin<&mut ServoPermissionRequest>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoPermissionRequest {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::{QString, QUrl};
+use servo::{
+    embedder_traits::{PermissionName, PermissionPrompt, PermissionRequest},
+    ipc_channel::ipc::IpcSender,
+};
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use url::{Origin, Url};
+
+/// The name of a permission as used by the Permissions API in the page
+fn feature_name(prompt: &PermissionPrompt) -> &'static str {
+    let (PermissionPrompt::Request(name) | PermissionPrompt::Insecure(name)) = prompt;
+    match name {
+        PermissionName::Geolocation => "geolocation",
+        PermissionName::Notifications => "notifications",
+        PermissionName::Push => "push",
+        PermissionName::Midi => "midi",
+        PermissionName::Camera => "camera",
+        PermissionName::Microphone => "microphone",
+        PermissionName::Speaker => "speaker",
+        PermissionName::DeviceInfo => "device-info",
+        PermissionName::BackgroundSync => "background-sync",
+        PermissionName::Bluetooth => "bluetooth",
+        PermissionName::PersistentStorage => "persistent-storage",
+    }
+}
+
+#[derive(Default)]
+struct QServoPermissionDecisions {
+    /// Decisions keyed by the serialised origin, then by the feature name
+    decisions: HashMap<String, HashMap<String, bool>>,
+    path: Option<PathBuf>,
+}
+
+impl QServoPermissionDecisions {
+    /// Read the decisions of a file, later lines replace earlier ones
+    fn read(path: &Path) -> HashMap<String, HashMap<String, bool>> {
+        let mut decisions: HashMap<String, HashMap<String, bool>> = HashMap::new();
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return decisions;
+        };
+
+        // Each line is "<origin> <feature> <granted|denied>" or "<origin> * cleared"
+        // which forgets every decision of the origin
+        for line in contents.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(origin), Some(feature), Some(decision)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+
+            if feature == "*" {
+                decisions.remove(origin);
+            } else {
+                decisions
+                    .entry(origin.to_owned())
+                    .or_default()
+                    .insert(feature.to_owned(), decision == "granted");
+            }
+        }
+        decisions
+    }
+
+    /// Write every decision, so that the file does not keep growing
+    fn write(&self) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+
+        let mut contents = String::new();
+        for (origin, features) in self.decisions.iter() {
+            for (feature, granted) in features.iter() {
+                contents.push_str(&decision_line(origin, feature, *granted));
+            }
+        }
+
+        if let Err(err) = std::fs::write(path, contents) {
+            println!("failed to save permission decisions: {err:?}");
+        }
+    }
+
+    /// Add a line to the file instead of writing every decision again
+    fn append(&self, line: &str) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()));
+        if let Err(err) = result {
+            println!("failed to save permission decision: {err:?}");
+        }
+    }
+}
+
+fn decision_line(origin: &str, feature: &str, granted: bool) -> String {
+    let decision = if granted { "granted" } else { "denied" };
+    format!("{origin} {feature} {decision}\n")
+}
+
+/// Remembered permission decisions, keyed by origin and feature
+///
+/// Clones share the same decisions, so policies set from QML are used by the Servo
+/// thread and decisions made in QML are remembered for later requests.
+#[derive(Clone, Default)]
+pub(crate) struct QServoPermissionStore {
+    inner: Arc<Mutex<QServoPermissionDecisions>>,
+}
+
+impl QServoPermissionStore {
+    /// Persist the decisions in the given file, or only keep them in memory
+    ///
+    /// The decisions of the file are loaded, decisions which were already made
+    /// replace them.
+    pub(crate) fn set_path(&self, path: Option<PathBuf>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.path == path {
+            return;
+        }
+
+        if let Some(path) = path.as_ref() {
+            let mut decisions = QServoPermissionDecisions::read(path);
+            for (origin, features) in inner.decisions.drain() {
+                decisions.entry(origin).or_default().extend(features);
+            }
+            inner.decisions = decisions;
+        }
+        inner.path = path;
+        inner.write();
+    }
+
+    /// Set the decision for a feature, such as "geolocation" or "camera", of an origin
+    pub(crate) fn set_policy(&self, origin: &Origin, feature: &str, granted: bool) {
+        let origin = origin.ascii_serialization();
+        let mut inner = self.inner.lock().unwrap();
+        inner.append(&decision_line(&origin, feature, granted));
+        inner
+            .decisions
+            .entry(origin)
+            .or_default()
+            .insert(feature.to_owned(), granted);
+    }
+
+    /// Forget all the decisions for an origin
+    pub(crate) fn clear_origin(&self, origin: &Origin) {
+        let origin = origin.ascii_serialization();
+        let mut inner = self.inner.lock().unwrap();
+        inner.append(&format!("{origin} * cleared\n"));
+        inner.decisions.remove(&origin);
+    }
+
+    pub(crate) fn decision(&self, origin: &Origin, feature: &str) -> Option<bool> {
+        self.inner
+            .lock()
+            .unwrap()
+            .decisions
+            .get(&origin.ascii_serialization())
+            .and_then(|features| features.get(feature))
+            .copied()
+    }
+}
+
+/// The answer channel of a permission request, remembering the decision if there is a store
+struct QServoPermissionResponder {
+    sender: IpcSender<PermissionRequest>,
+    origin: Option<Origin>,
+    feature: &'static str,
+    store: Option<QServoPermissionStore>,
+    /// Whether the page is a secure context, decisions of others are not remembered
+    secure: bool,
+}
+
+impl QServoPermissionResponder {
+    fn respond(self, granted: bool) {
+        if let (Some(store), Some(origin)) = (self.store.as_ref(), self.origin.as_ref()) {
+            if self.secure {
+                store.set_policy(origin, self.feature, granted);
+            }
+        }
+
+        self.respond_once(granted);
+    }
+
+    /// Answer without remembering the decision
+    fn respond_once(self, granted: bool) {
+        let request = if granted {
+            PermissionRequest::Granted
+        } else {
+            PermissionRequest::Denied
+        };
+        if let Err(err) = self.sender.send(request) {
+            println!("failed to answer permission request: {err:?}");
+        }
+    }
+}
+
+/// A permission requested by the page which has not been shown to Qt yet
+pub(crate) struct QServoPermissionRequest {
+    responder: QServoPermissionResponder,
+}
+
+impl QServoPermissionRequest {
+    pub(crate) fn new(
+        prompt: PermissionPrompt,
+        url: Option<&Url>,
+        sender: IpcSender<PermissionRequest>,
+        store: Option<QServoPermissionStore>,
+    ) -> Self {
+        Self {
+            responder: QServoPermissionResponder {
+                sender,
+                origin: url.map(Url::origin),
+                feature: feature_name(&prompt),
+                store,
+                secure: matches!(prompt, PermissionPrompt::Request(_)),
+            },
+        }
+    }
+
+    /// The remembered decision for this origin and feature if there is one
+    pub(crate) fn stored_decision(&self) -> Option<bool> {
+        let responder = &self.responder;
+        responder
+            .store
+            .as_ref()
+            .zip(responder.origin.as_ref())
+            .and_then(|(store, origin)| store.decision(origin, responder.feature))
+    }
+
+    pub(crate) fn respond(self, granted: bool) {
+        self.responder.respond(granted);
+    }
+
+    /// Deny without remembering, used when there is no handler in Qt
+    pub(crate) fn deny(self) {
+        self.responder.respond_once(false);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoPermissionRequest> {
+        let mut request = qobject::servo_permission_request_unique_ptr();
+        request
+            .pin_mut()
+            .set_feature(QString::from(self.responder.feature));
+        if let Some(origin) = self.responder.origin.as_ref() {
+            if let Ok(origin) = Url::parse(&origin.ascii_serialization()) {
+                request.pin_mut().set_origin(QUrl::from(&origin));
+            }
+        }
+        request.pin_mut().rust_mut().responder = Some(self.responder);
+        request
+    }
+}
+
+#[derive(Default)]
+pub struct QServoPermissionRequestRust {
+    feature: QString,
+    origin: QUrl,
+    responder: Option<QServoPermissionResponder>,
+}
+
+impl Drop for QServoPermissionRequestRust {
+    fn drop(&mut self) {
+        // Never leave the page waiting for an answer
+        if let Some(responder) = self.responder.take() {
+            responder.respond_once(false);
+        }
+    }
+}
+
+impl qobject::ServoPermissionRequest {
+    /// Whether the request has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.responder.is_none()
+    }
+
+    fn grant(mut self: Pin<&mut Self>) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(true);
+        }
+    }
+
+    fn deny(mut self: Pin<&mut Self>) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(false);
+        }
+    }
+}
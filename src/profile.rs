@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servoprofile")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+    }
+
+    unsafe extern "RustQt" {
+        /// Where a web view keeps its cookies and local storage
+        ///
+        /// A profile is read when Servo starts, so the web view restarts Servo when its
+        /// profile or a property of the profile changes. Web views with different storage
+        /// paths do not share cookies or storage.
+        ///
+        /// There is no cachePath, as Servo only keeps its HTTP cache in memory. So the
+        /// cache is never persisted and is lost when Servo restarts.
+        #[qobject]
+        #[qml_element]
+        #[qproperty(bool, off_the_record)]
+        #[qproperty(QString, storage_path)]
+        type ServoProfile = super::QServoProfileRust;
+    }
+}
+
+use core::pin::Pin;
+use std::{path::PathBuf, sync::Mutex};
+
+use cxx_qt::{CxxQtThread, QMetaObjectConnectionGuard};
+use cxx_qt_lib::QString;
+use servo::servo_config::opts;
+
+use crate::webview::qobject::ServoWebView;
+
+/// Where Servo keeps its state, by default nothing is persisted
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QServoProfile {
+    /// The directory for cookies, local storage, permission decisions and other
+    /// persisted state
+    pub storage_path: Option<PathBuf>,
+    /// Never write anything to disk, the paths are ignored
+    pub off_the_record: bool,
+}
+
+impl QServoProfile {
+    /// The directory which Servo should persist its state into
+    pub(crate) fn config_dir(&self) -> Option<PathBuf> {
+        if self.off_the_record {
+            return None;
+        }
+
+        let storage_path = self.storage_path.clone()?;
+        if let Err(err) = std::fs::create_dir_all(&storage_path) {
+            println!("failed to create profile directory {storage_path:?}: {err:?}");
+            return None;
+        }
+        Some(storage_path)
+    }
+
+    /// The file which remembered permission decisions are kept in
+    pub(crate) fn permissions_path(&self) -> Option<PathBuf> {
+        self.config_dir().map(|dir| dir.join("permissions"))
+    }
+
+    /// Create Servo with this profile
+    ///
+    /// Servo's options are global to the process, so the Servo threads of each web view
+    /// take turns to set the config directory. Servo only reads it while it is created,
+    /// when its resource threads start, so each keeps the directory it was created with.
+    ///
+    /// TODO: Servo expects a single instance per process, the rest of its options and
+    /// its preferences are shared by every web view
+    pub(crate) fn create_servo<T>(&self, create: impl FnOnce() -> T) -> T {
+        static CREATE_SERVO: Mutex<()> = Mutex::new(());
+        let _guard = CREATE_SERVO
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut options = opts::get().clone();
+        options.config_dir = self.config_dir();
+        opts::set_options(options);
+
+        create()
+    }
+}
+
+#[derive(Default)]
+pub struct QServoProfileRust {
+    off_the_record: bool,
+    storage_path: QString,
+}
+
+impl qobject::ServoProfile {
+    pub(crate) fn profile(&self) -> QServoProfile {
+        let path = |path: &QString| {
+            Some(PathBuf::from(path.to_string())).filter(|path| !path.as_os_str().is_empty())
+        };
+
+        QServoProfile {
+            storage_path: path(&self.storage_path),
+            off_the_record: self.off_the_record,
+        }
+    }
+
+    /// Send the values of the profile to the web view whenever they change, until the
+    /// returned connections are dropped
+    ///
+    /// The web view ignores values of a profile which is no longer assigned to it.
+    pub(crate) fn watch(
+        mut self: Pin<&mut Self>,
+        qt_thread: CxxQtThread<ServoWebView>,
+        generation: u64,
+    ) -> Vec<QMetaObjectConnectionGuard> {
+        let changed = move |profile: Pin<&mut Self>| {
+            let profile = profile.as_ref().profile();
+            if qt_thread
+                .queue(move |webview| webview.servo_profile_changed(generation, profile))
+                .is_err()
+            {
+                println!("failed to queue profile change, the web view has gone");
+            }
+        };
+        vec![
+            self.as_mut().on_off_the_record_changed(changed.clone()),
+            self.as_mut().on_storage_path_changed(changed),
+        ]
+    }
+}
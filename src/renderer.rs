@@ -28,6 +28,9 @@ pub(crate) mod qobject {
             source: UniquePtr<QOpenGLFramebufferObject>,
         );
 
+        #[cxx_name = "clearFramebuffer"]
+        unsafe fn clear_framebuffer(target: *mut QOpenGLFramebufferObject);
+
         include!("cxx-qt-lib/qsize.h");
         type QSize = cxx_qt_lib::QSize;
 
@@ -58,35 +61,82 @@ pub(crate) mod qobject {
 }
 
 use crate::{
+    authenticationrequest::QServoCredentialStore,
+    browsingdata::{remove_persisted_data, QServoBrowsingDataTypes},
+    content::QServoContent,
+    cookies::QServoCookieRequest,
+    errorpage::QServoErrorPage,
+    messagechannel::QServoMessageChannel,
+    permissionrequest::QServoPermissionStore,
     servothread::{QServoMessage, QServoThread},
+    settings::QServoSettings,
+    urlscheme::QServoUrlSchemeHandlers,
+    webresourcerequest::{QServoCannedResponses, QServoRequestInterceptor},
     webview::qobject::ServoWebView,
+    windowheadless::QServoScreenGeometry,
 };
 use core::pin::Pin;
 use cxx_qt::{CxxQtType, Threading};
 use cxx_qt_lib::{QSize, QUrl};
 use euclid::Size2D;
 use servo::{compositing::windowing::EmbedderEvent, servo_url::ServoUrl};
-use std::sync::mpsc::{self, Sender};
+use std::{
+    sync::{
+        mpsc::{self, Sender},
+        Arc,
+    },
+    time::Duration,
+};
 use surfman::{Context, Device};
 
 #[derive(Default)]
 pub struct QServoRendererRust {
+    /// The audio muted and volume last sent to Servo
+    audio: Option<(bool, f64)>,
+    /// Whether there is no web view, so that the content should be blank
+    blank: bool,
+    /// Whether Servo or the content crashed, so that the last frame is kept
+    terminated: bool,
+    /// The state last sent to Servo, kept so that it can be sent again after a restart
+    credential_store: Option<QServoCredentialStore>,
+    error_page: Option<QServoErrorPage>,
+    file_chooser_result: Option<Vec<String>>,
+    message_channel: QServoMessageChannel,
+    permission_store: Option<QServoPermissionStore>,
+    url_scheme_handlers: QServoUrlSchemeHandlers,
+    canned_responses: QServoCannedResponses,
+    settings: QServoSettings,
+    request_interceptor: Option<Arc<dyn QServoRequestInterceptor>>,
+    /// Whether QML intercepts requests, as last sent to Servo
+    intercept_requests_in_qml: Option<bool>,
     size: QSize,
+    screen_geometry: Option<QServoScreenGeometry>,
     url: QUrl,
     servo_sender: Option<Sender<QServoMessage>>,
     qt_gl: Option<(Device, Context)>,
 }
 
+/// Ask the Servo thread to shut down and wait so that its state is persisted
+///
+/// The Servo thread could have panicked already, then there is nothing to wait for.
+fn quit_servo(servo_sender: &Sender<QServoMessage>) {
+    let (quit_sender, quit_receiver) = mpsc::sync_channel(1);
+    if servo_sender.send(QServoMessage::Quit(quit_sender)).is_ok() {
+        // Servo waits for itself too, this is only in case the thread is stuck
+        let _ = quit_receiver.recv_timeout(Duration::from_secs(10));
+    }
+}
+
 impl Drop for QServoRendererRust {
     fn drop(&mut self) {
-        self.servo_sender
-            .as_ref()
-            .unwrap()
-            .send(QServoMessage::Quit)
-            .unwrap();
+        if let Some(servo_sender) = self.servo_sender.as_ref() {
+            quit_servo(servo_sender);
+        }
 
         if let Some((device, mut context)) = self.qt_gl.take() {
-            device.destroy_context(&mut context).unwrap();
+            if let Err(err) = device.destroy_context(&mut context) {
+                println!("failed to destroy context: {err:?}");
+            }
         }
     }
 }
@@ -96,17 +146,40 @@ impl qobject::QServoRenderer {
         qobject::qservo_renderer_unique_ptr()
     }
 
+    /// Send a message to the Servo thread, returns false if the thread has gone
+    fn send(&self, message: QServoMessage) -> bool {
+        self.servo_sender
+            .as_ref()
+            .map(|servo_sender| servo_sender.send(message).is_ok())
+            .unwrap_or(false)
+    }
+
     fn render(mut self: Pin<&mut Self>) {
         println!("render start");
 
+        // Don't show a stale frame when there is no web view
+        if self.blank {
+            unsafe { qobject::clear_framebuffer(self.as_ref().framebuffer_object()) };
+            println!("render end");
+            return;
+        }
+
+        // Keep the last frame after a crash, until there is a restart
+        if self.terminated {
+            println!("render end");
+            return;
+        }
+
         // Ask to borrow a surface
         let (take_sender, take_receiver) = mpsc::sync_channel(0);
         let (recycle_sender, recycle_receiver) = mpsc::sync_channel(0);
-        self.servo_sender
+        if !self
             .as_ref()
-            .unwrap()
             .send(QServoMessage::BorrowSurface(take_sender, recycle_receiver))
-            .unwrap();
+        {
+            println!("render end");
+            return;
+        }
 
         // Wait for the response from the background thread
         let surface = take_receiver.recv();
@@ -134,18 +207,18 @@ impl qobject::QServoRenderer {
 
                         // Destory the texture and return the surface back to the background thread
                         let surface = device.destroy_surface_texture(context, texture);
-                        recycle_sender.send(surface.ok()).unwrap();
+                        let _ = recycle_sender.send(surface.ok());
                     }
                     Err((_, surface)) => {
                         // Return the surface back to the background thread
-                        recycle_sender.send(Some(surface)).unwrap();
+                        let _ = recycle_sender.send(Some(surface));
                     }
                 }
             } else {
-                recycle_sender.send(Some(surface)).unwrap();
+                let _ = recycle_sender.send(Some(surface));
             }
         } else {
-            recycle_sender.send(None).unwrap();
+            let _ = recycle_sender.send(None);
         }
 
         println!("render end");
@@ -157,6 +230,36 @@ impl qobject::QServoRenderer {
         if let Some(webview_ref) = webview_ptr.as_mut() {
             let mut webview = Pin::new_unchecked(webview_ref);
 
+            // Tear down the Servo worker thread, a new one is started below once the
+            // old one has persisted its state, as both would use the same profile
+            if std::mem::take(&mut webview.as_mut().rust_mut().restart_requested) {
+                if let Some(servo_sender) = self.as_mut().rust_mut().servo_sender.take() {
+                    quit_servo(&servo_sender);
+                }
+
+                // Servo has written its state by now, so it can be removed for good
+                if let Some(types) = webview.as_mut().rust_mut().browsing_data_wipe.take() {
+                    let config_dir = webview.as_ref().servo_profile().config_dir();
+                    let cleared = remove_persisted_data(config_dir.as_deref(), types);
+                    if webview
+                        .qt_thread()
+                        .queue(move |webview| webview.browsing_data_cleared_with(cleared))
+                        .is_err()
+                    {
+                        println!("failed to queue cleared browsing data, the web view has gone");
+                    }
+                }
+            }
+
+            // Preferences are applied when Servo starts and again at runtime
+            let settings_changed =
+                if let Some(settings) = webview.as_mut().rust_mut().servo_settings.take() {
+                    self.as_mut().rust_mut().settings = settings;
+                    true
+                } else {
+                    false
+                };
+
             // Start the Servo worker thread if there isn't one
             if self.as_ref().servo_sender.is_none() {
                 let qt_thread = webview.qt_thread();
@@ -179,80 +282,260 @@ impl qobject::QServoRenderer {
                 let size = webview.as_ref().size().to_size();
                 let size = Size2D::new(size.width() as u32, size.height() as u32);
 
-                std::thread::spawn(move || {
-                    QServoThread::new(servo_receiver, qt_thread, connection, size).run()
-                });
+                // Servo only reads the user agent when it starts, an empty one is its default
+                //
+                // TODO: Servo has no way for an embedder to add request headers yet, so it
+                // always sends its own Accept-Language, see ServoWebView
+                let user_agent = Some(webview.as_ref().user_agent().to_string())
+                    .filter(|user_agent| !user_agent.is_empty());
+
+                let profile = webview.as_ref().servo_profile();
+                webview.as_ref().permissions.set_path(profile.permissions_path());
+
+                QServoThread::spawn(
+                    servo_receiver,
+                    qt_thread,
+                    connection,
+                    size,
+                    user_agent,
+                    profile,
+                    self.settings.clone(),
+                );
+
+                // Everything which was sent to a previous thread needs to be sent again
+                let mut rust = self.as_mut().rust_mut();
+                rust.servo_sender = Some(servo_sender);
+                rust.audio = None;
+                rust.screen_geometry = None;
+                rust.size = QSize::default();
+                rust.url = QUrl::default();
+                rust.intercept_requests_in_qml = None;
+
+                let mut webview_rust = webview.as_mut().rust_mut();
+                webview_rust
+                    .credential_store
+                    .get_or_insert_with(|| self.credential_store.clone());
+                webview_rust
+                    .error_page
+                    .get_or_insert_with(|| self.error_page.clone());
+                webview_rust
+                    .file_chooser_result
+                    .get_or_insert_with(|| self.file_chooser_result.clone());
+                webview_rust
+                    .message_channel
+                    .get_or_insert_with(|| self.message_channel.clone());
+                webview_rust
+                    .permission_store
+                    .get_or_insert_with(|| self.permission_store.clone());
+                webview_rust
+                    .url_scheme_handlers
+                    .get_or_insert_with(|| self.url_scheme_handlers.clone());
+                webview_rust
+                    .canned_responses
+                    .get_or_insert_with(|| self.canned_responses.clone());
+            }
+
+            if settings_changed {
+                self.as_ref()
+                    .send(QServoMessage::Settings(self.settings.clone()));
+            }
+
+            // Handlers need to be known before a URL of their scheme is loaded
+            if let Some(handlers) = webview.as_mut().rust_mut().url_scheme_handlers.take() {
+                self.as_mut().rust_mut().url_scheme_handlers = handlers.clone();
+                self.as_ref()
+                    .send(QServoMessage::UrlSchemeHandlers(handlers));
+            }
+            if let Some(responses) = webview.as_mut().rust_mut().canned_responses.take() {
+                self.as_mut().rust_mut().canned_responses = responses.clone();
+                self.as_ref()
+                    .send(QServoMessage::CannedResponses(responses));
+            }
 
-                self.as_mut().rust_mut().servo_sender = Some(servo_sender);
+            // Interception needs to be known before a URL is loaded
+            let interceptor_changed =
+                if let Some(interceptor) = webview.as_mut().rust_mut().request_interceptor.take() {
+                    self.as_mut().rust_mut().request_interceptor = interceptor;
+                    true
+                } else {
+                    false
+                };
+            let in_qml = webview.as_ref().intercepts_requests_in_qml();
+            if interceptor_changed || self.intercept_requests_in_qml != Some(in_qml) {
+                self.as_mut().rust_mut().intercept_requests_in_qml = Some(in_qml);
+                self.as_ref().send(QServoMessage::RequestInterception(
+                    self.request_interceptor.clone(),
+                    in_qml,
+                ));
             }
 
             // Check if we have a new URL
+            //
+            // Note that an empty URL is stored too, so that the same URL can be loaded
+            // again after the web view has been closed
             let url = webview.as_ref().url().clone();
-            if url != self.url && url.is_valid() {
+            if url != self.url {
                 self.as_mut().rust_mut().url = url;
 
                 let new_url = url::Url::try_from(&self.url);
-                if new_url.is_ok() {
+                if self.url.is_valid() && new_url.is_ok() {
                     let servo_url = ServoUrl::from_url(new_url.unwrap());
-                    self.as_ref()
-                        .servo_sender
-                        .as_ref()
-                        .unwrap()
-                        .send(QServoMessage::Url(servo_url))
-                        .unwrap();
+                    self.as_ref().send(QServoMessage::Url(servo_url));
                 }
             }
 
+            let content_requests: Vec<QServoContent> = webview
+                .as_mut()
+                .rust_mut()
+                .content_requests
+                .drain(..)
+                .collect();
+            for content in content_requests {
+                self.as_ref().send(QServoMessage::LoadContent(content));
+            }
+
+            let blank = webview.as_ref().blank;
+            self.as_mut().rust_mut().blank = blank;
+            let terminated = webview.as_ref().terminated;
+            self.as_mut().rust_mut().terminated = terminated;
+
             let size = webview.as_ref().size().to_size();
             if size != self.size {
                 self.as_mut().rust_mut().size = size;
 
+                self.as_ref().send(QServoMessage::Resize(Size2D::new(
+                    self.size.width(),
+                    self.size.height(),
+                )));
+            }
+
+            let audio = (*webview.as_ref().audio_muted(), *webview.as_ref().volume());
+            if Some(audio) != self.audio {
+                self.as_mut().rust_mut().audio = Some(audio);
+
+                let (muted, volume) = audio;
+                self.as_ref().send(QServoMessage::Audio(muted, volume));
+            }
+
+            // Tell Servo where the window is so that window.screenX etc are correct
+            let screen_geometry = Some(webview.as_ref().screen_geometry());
+            if screen_geometry != self.screen_geometry {
+                self.as_mut().rust_mut().screen_geometry = screen_geometry;
+
                 self.as_ref()
-                    .servo_sender
-                    .as_ref()
-                    .unwrap()
-                    .send(QServoMessage::Resize(Size2D::new(
-                        self.size.width(),
-                        self.size.height(),
-                    )))
-                    .unwrap();
+                    .send(QServoMessage::ScreenGeometry(screen_geometry.unwrap()));
             }
 
             if let Some(direction) = webview.as_mut().rust_mut().navigation_direction.take() {
+                self.as_ref().send(QServoMessage::Navigation(direction));
+            }
+
+            if let Some(credential_store) = webview.as_mut().rust_mut().credential_store.take() {
+                self.as_mut().rust_mut().credential_store = credential_store.clone();
+                self.as_ref()
+                    .send(QServoMessage::CredentialStore(credential_store));
+            }
+
+            if let Some(file_chooser_result) =
+                webview.as_mut().rust_mut().file_chooser_result.take()
+            {
+                self.as_mut().rust_mut().file_chooser_result = file_chooser_result.clone();
+                self.as_ref()
+                    .send(QServoMessage::FileChooserResult(file_chooser_result));
+            }
+
+            if let Some(permission_store) = webview.as_mut().rust_mut().permission_store.take() {
+                self.as_mut().rust_mut().permission_store = permission_store.clone();
+                self.as_ref()
+                    .send(QServoMessage::PermissionStore(permission_store));
+            }
+
+            if let Some(error_page) = webview.as_mut().rust_mut().error_page.take() {
+                self.as_mut().rust_mut().error_page = error_page.clone();
+                self.as_ref().send(QServoMessage::ErrorPage(error_page));
+            }
+
+            if let Some(bypass_cache) = webview.as_mut().rust_mut().reload_requested.take() {
+                self.as_ref().send(QServoMessage::Reload(bypass_cache));
+            }
+
+            if let Some(full_screen) = webview.as_mut().rust_mut().page_full_screen_change.take()
+            {
+                self.as_ref().send(QServoMessage::FullScreen(full_screen));
+            }
+
+            if std::mem::take(&mut webview.as_mut().rust_mut().close_requested) {
+                self.as_ref().send(QServoMessage::Close);
+            }
+
+            if std::mem::take(&mut webview.as_mut().rust_mut().stop_requested) {
+                self.as_ref().send(QServoMessage::Stop);
+            }
+
+            if let Some(message_channel) = webview.as_mut().rust_mut().message_channel.take() {
+                self.as_mut().rust_mut().message_channel = message_channel.clone();
                 self.as_ref()
-                    .servo_sender
-                    .as_ref()
-                    .unwrap()
-                    .send(QServoMessage::Navigation(direction))
-                    .unwrap();
+                    .send(QServoMessage::MessageChannel(message_channel));
+            }
+
+            let posted_messages: Vec<String> = webview
+                .as_mut()
+                .rust_mut()
+                .posted_messages
+                .drain(..)
+                .collect();
+            for json in posted_messages {
+                self.as_ref().send(QServoMessage::PostMessage(json));
+            }
+
+            let javascript_requests: Vec<(u64, String)> = webview
+                .as_mut()
+                .rust_mut()
+                .javascript_requests
+                .drain(..)
+                .collect();
+            for (id, script) in javascript_requests {
+                self.as_ref().send(QServoMessage::RunJavaScript(id, script));
+            }
+
+            let cookie_requests: Vec<QServoCookieRequest> = webview
+                .as_mut()
+                .rust_mut()
+                .cookie_requests
+                .drain(..)
+                .collect();
+            for request in cookie_requests {
+                self.as_ref().send(QServoMessage::Cookie(request));
+            }
+
+            let browsing_data_requests: Vec<QServoBrowsingDataTypes> = webview
+                .as_mut()
+                .rust_mut()
+                .browsing_data_requests
+                .drain(..)
+                .collect();
+            for types in browsing_data_requests {
+                self.as_ref().send(QServoMessage::ClearBrowsingData(types));
             }
 
             // Process any converted events from Qt
             let events: Vec<EmbedderEvent> = webview.as_mut().rust_mut().events.drain(..).collect();
             for event in events.into_iter() {
-                self.as_ref()
-                    .servo_sender
-                    .as_ref()
-                    .unwrap()
-                    .send(QServoMessage::RawEmbeddedEvent(event))
-                    .unwrap();
+                self.as_ref().send(QServoMessage::RawEmbeddedEvent(event));
             }
 
             // Process any pending events
             let navigation_allowed = *webview.as_ref().navigation_allowed();
             let (heartbeat_sender, heartbeat_receiver) = mpsc::sync_channel(0);
-            self.as_ref()
-                .servo_sender
-                .as_ref()
-                .unwrap()
-                .send(QServoMessage::Heartbeat(
-                    heartbeat_sender,
-                    navigation_allowed,
-                ))
-                .unwrap();
+            self.as_ref().send(QServoMessage::Heartbeat(
+                heartbeat_sender,
+                navigation_allowed,
+            ));
             // Wait for response, otherwise if we enter render() while the
             // heartbeat is running flickering can occur
-            heartbeat_receiver.recv().unwrap();
+            //
+            // If the Servo thread has gone then there is nothing to wait for
+            let _ = heartbeat_receiver.recv();
         }
 
         println!("sync end");
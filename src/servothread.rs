@@ -4,16 +4,24 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
     collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
     rc::Rc,
-    sync::mpsc::{Receiver, SyncSender},
+    sync::{
+        mpsc::{Receiver, SendError, SyncSender},
+        Arc, Mutex, Once,
+    },
+    time::{Duration, Instant},
 };
 
 use cxx_qt::CxxQtThread;
 use cxx_qt_lib::{QString, QUrl};
 use servo::{
     compositing::windowing::{EmbedderEvent, WindowMethods},
-    embedder_traits::EventLoopWaker,
+    embedder_traits::{EmbedderMsg, EventLoopWaker},
     euclid::Size2D,
     servo_geometry::DeviceIndependentPixel,
     servo_url::ServoUrl,
@@ -25,24 +33,108 @@ use surfman::{Connection, Surface};
 use url::Url;
 
 use crate::{
-    browser::QServoBrowser, embedder::QServoEmbedder, events_loop::QServoEventsLoopWaker,
-    webview::qobject::ServoWebView, windowheadless::QServoWindowHeadless,
+    audio::QServoAudio,
+    authenticationrequest::QServoCredentialStore,
+    browser::QServoBrowser,
+    browsingdata::QServoBrowsingDataTypes,
+    content::QServoContent,
+    cookies::QServoCookieRequest,
+    embedder::QServoEmbedder,
+    errorpage::QServoErrorPage,
+    events_loop::QServoEventsLoopWaker,
+    javascript::{execute_async_script, run_javascript},
+    messagechannel::QServoMessageChannel,
+    permissionrequest::QServoPermissionStore,
+    profile::QServoProfile,
+    settings::QServoSettings,
+    urlscheme::QServoUrlSchemeHandlers,
+    webresourcerequest::{QServoCannedResponses, QServoRequestInterceptor},
+    webview::qobject::ServoWebView,
+    windowheadless::{QServoScreenGeometry, QServoWindowHeadless},
 };
 
+/// How long to wait for Servo to persist its state when it is shut down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for the document and its resources to be fetched past the cache
+/// before reloading anyway
+const REFRESH_CACHE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Script which fetches the document and its resources past the HTTP cache
+///
+/// It is wrapped in a function so that nothing is left in the global scope of the page.
+const REFRESH_CACHE_SCRIPT: &str = r#"(() => {
+    const urls = [location.href].concat(
+        performance.getEntriesByType("resource").map((entry) => entry.name)
+    );
+    Promise.allSettled(urls.map((url) => fetch(url, { cache: "reload", mode: "no-cors" })))
+        .then(() => window.webdriverCallback(null));
+})();"#;
+
 // #[derive(Debug)]
 pub(crate) enum QServoMessage {
+    Audio(bool, f64),
+    CannedResponses(QServoCannedResponses),
+    ClearBrowsingData(QServoBrowsingDataTypes),
+    Close,
+    Cookie(QServoCookieRequest),
+    CredentialStore(Option<QServoCredentialStore>),
+    ErrorPage(Option<QServoErrorPage>),
+    FileChooserResult(Option<Vec<String>>),
+    FullScreen(bool),
+    LoadContent(QServoContent),
+    MessageChannel(QServoMessageChannel),
     Navigation(i32),
+    PermissionStore(Option<QServoPermissionStore>),
+    PostMessage(String),
     RawEmbeddedEvent(EmbedderEvent),
+    Reload(bool),
+    RequestInterception(Option<Arc<dyn QServoRequestInterceptor>>, bool),
     Resize(Size2D<i32, DevicePixel>),
+    RunJavaScript(u64, String),
+    ScreenGeometry(QServoScreenGeometry),
+    Settings(QServoSettings),
     Url(ServoUrl),
+    UrlSchemeHandlers(QServoUrlSchemeHandlers),
     Heartbeat(SyncSender<()>, bool),
     BorrowSurface(SyncSender<Option<Surface>>, Receiver<Option<Surface>>),
-    Quit,
+    Stop,
+    /// Shut Servo down, the sender is told once it has
+    Quit(SyncSender<()>),
 }
 
 unsafe impl Send for QServoMessage {}
 
+thread_local! {
+    /// The backtrace of the last panic on this thread
+    static PANIC_BACKTRACE: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Reload the web view unless it has been reloaded already
+fn reload_once(qt_thread: &Mutex<Option<CxxQtThread<ServoWebView>>>) {
+    if let Some(qt_thread) = qt_thread.lock().unwrap().take() {
+        if qt_thread.queue(|webview| webview.reload()).is_err() {
+            println!("failed to queue reload, the web view has gone");
+        }
+    }
+}
+
+/// Remember the backtrace of a panic so that it can be given to Qt
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            PANIC_BACKTRACE.with(|backtrace| {
+                backtrace.replace(Some(Backtrace::force_capture().to_string()));
+            });
+            default_hook(info);
+        }));
+    });
+}
+
 pub(crate) struct QServoThread {
+    audio: QServoAudio,
     browser: QServoBrowser,
     browser_id: TopLevelBrowsingContextId,
     servo: Servo<QServoWindowHeadless>,
@@ -51,17 +143,67 @@ pub(crate) struct QServoThread {
 }
 
 impl QServoThread {
-    pub(crate) fn new(
+    /// Start a Servo thread, if it panics then Qt is told so that it can be restarted
+    pub(crate) fn spawn(
+        receiver: Receiver<QServoMessage>,
+        qt_thread: CxxQtThread<ServoWebView>,
+        connection: Connection,
+        size: Size2D<u32, DeviceIndependentPixel>,
+        user_agent: Option<String>,
+        profile: QServoProfile,
+        settings: QServoSettings,
+    ) {
+        install_panic_hook();
+
+        std::thread::spawn(move || {
+            let panic_qt_thread = qt_thread.clone();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                QServoThread::new(
+                    receiver, qt_thread, connection, size, user_agent, profile, settings,
+                )
+                .run()
+            }));
+
+            if let Err(payload) = result {
+                let reason = if let Some(reason) = payload.downcast_ref::<&str>() {
+                    reason.to_string()
+                } else if let Some(reason) = payload.downcast_ref::<String>() {
+                    reason.to_owned()
+                } else {
+                    "Servo panicked".to_owned()
+                };
+                let backtrace = PANIC_BACKTRACE
+                    .with(|backtrace| backtrace.take())
+                    .unwrap_or_default();
+
+                if panic_qt_thread
+                    .queue(move |webview| {
+                        webview.render_process_terminated_with(&reason, &backtrace, true);
+                    })
+                    .is_err()
+                {
+                    println!("failed to report panic, the web view has gone");
+                }
+            }
+        });
+    }
+
+    fn new(
         receiver: Receiver<QServoMessage>,
         qt_thread: CxxQtThread<ServoWebView>,
         connection: Connection,
         size: Size2D<u32, DeviceIndependentPixel>,
+        user_agent: Option<String>,
+        profile: QServoProfile,
+        settings: QServoSettings,
     ) -> Self {
+        // Some preferences are only read when Servo starts
+        settings.apply();
+
         let event_loop_waker = QServoEventsLoopWaker::new(qt_thread.clone());
         let embedder = Box::new(QServoEmbedder::new(event_loop_waker.clone_box()));
 
         let window = Rc::new(QServoWindowHeadless::new(size, connection));
-        let user_agent = None;
         // The in-process interface to Servo.
         //
         // It does everything necessary to render the web, primarily
@@ -73,34 +215,80 @@ impl QServoThread {
         // application Servo is embedded in. Clients then create an event
         // loop to pump messages between the embedding application and
         // various browser components.
-        let servo_data = Servo::new(
-            embedder,
-            window.clone(),
-            user_agent,
-            servo::compositing::CompositeTarget::Window,
-        );
+        let servo_data = profile.create_servo(|| {
+            Servo::new(
+                embedder,
+                window.clone(),
+                user_agent,
+                servo::compositing::CompositeTarget::Window,
+            )
+        });
 
         // Enable logging and store servo instance
-        servo_data.servo.setup_logging();
+        //
+        // The logger can only be set once, so skip this when Servo is restarted
+        static SETUP_LOGGING: Once = Once::new();
+        SETUP_LOGGING.call_once(|| servo_data.servo.setup_logging());
 
         // Initialise servo
         event_loop_waker.wake();
 
         Self {
+            audio: QServoAudio::default(),
             browser_id: servo_data.browser_id,
             servo: servo_data.servo,
-            browser: QServoBrowser::default(),
+            browser: QServoBrowser::new(event_loop_waker.clone_box()),
             receiver,
             qt_thread,
         }
     }
 
-    pub(crate) fn run(&mut self) {
+    /// Queue an update to the web view, which could have been destroyed already
+    fn queue_qt(&self, f: impl FnOnce(Pin<&mut ServoWebView>) + Send + 'static) {
+        if self.qt_thread.queue(f).is_err() {
+            println!("failed to queue update, the web view has gone");
+        }
+    }
+
+    pub(crate) fn run(mut self) {
         let mut current_url = None;
         let mut favicons = HashMap::<Url, Url>::new();
 
         while let Ok(msg) = self.receiver.recv() {
             match msg {
+                QServoMessage::Audio(muted, volume) => {
+                    self.audio.set_muted(muted);
+                    self.browser.set_audio(muted, volume);
+                }
+                QServoMessage::Close => {
+                    if !self.browser.close() {
+                        // There is nothing to close so the host can continue
+                        self.queue_qt(|mut webview| {
+                            webview.as_mut().window_close_requested();
+                        });
+                    }
+                }
+                QServoMessage::CredentialStore(credential_store) => {
+                    self.browser.set_credential_store(credential_store);
+                }
+                QServoMessage::ErrorPage(error_page) => {
+                    self.browser.set_error_page(error_page);
+                }
+                QServoMessage::FullScreen(full_screen) => {
+                    self.browser.set_full_screen(full_screen);
+                }
+                QServoMessage::FileChooserResult(files) => {
+                    self.browser.set_file_chooser_result(files);
+                }
+                QServoMessage::MessageChannel(message_channel) => {
+                    self.browser.set_message_channel(message_channel);
+                }
+                QServoMessage::PermissionStore(permission_store) => {
+                    self.browser.set_permission_store(permission_store);
+                }
+                QServoMessage::PostMessage(json) => {
+                    self.browser.post_message(&json);
+                }
                 QServoMessage::Navigation(direction) => {
                     let direction = if direction < 0 {
                         servo::msg::constellation_msg::TraversalDirection::Back(
@@ -118,13 +306,95 @@ impl QServoThread {
                 QServoMessage::RawEmbeddedEvent(event) => {
                     self.browser.push_event(event);
                 }
+                QServoMessage::RequestInterception(interceptor, in_qml) => {
+                    self.browser.set_request_interception(interceptor, in_qml);
+                }
+                QServoMessage::Reload(bypass_cache) => {
+                    if let Some(webview_id) = self.browser.webview_id() {
+                        // Servo has no cache mode for a reload, so fetch the document and
+                        // its resources past the cache first, which replaces their cached
+                        // responses, and reload once that has finished
+                        if bypass_cache {
+                            // The page could go away before the script answers, so
+                            // whichever of the answer and the timeout is first reloads
+                            let reload = Arc::new(Mutex::new(Some(self.qt_thread.clone())));
+                            let refreshed = reload.clone();
+                            let refresh = execute_async_script(
+                                webview_id,
+                                REFRESH_CACHE_SCRIPT.to_owned(),
+                                move |_| reload_once(&refreshed),
+                            );
+                            match refresh {
+                                Ok(event) => {
+                                    self.browser.push_event(event);
+                                    std::thread::spawn(move || {
+                                        std::thread::sleep(REFRESH_CACHE_TIMEOUT);
+                                        reload_once(&reload);
+                                    });
+                                    continue;
+                                }
+                                Err(err) => println!("failed to bypass the cache: {err}"),
+                            }
+                        }
+
+                        self.browser.push_event(EmbedderEvent::Reload(webview_id));
+                    }
+                }
+                QServoMessage::Stop => {
+                    if let Some(request) = self.browser.stop_load() {
+                        self.queue_qt(move |mut webview| {
+                            webview.as_mut().set_loading(false);
+                            webview.as_mut().load_request_changed_with(&request);
+                        });
+                    }
+                }
                 QServoMessage::Resize(size) => {
                     let surfman = self.servo.window().rendering_context();
-                    surfman
-                        .resize(size.to_untyped().to_i32())
-                        .expect("Failed to resize");
+                    if let Err(err) = surfman.resize(size.to_untyped().to_i32()) {
+                        println!("failed to resize: {err:?}");
+                        continue;
+                    }
                     self.browser.push_event(EmbedderEvent::Resize);
                 }
+                QServoMessage::ClearBrowsingData(types) => {
+                    // The rest of the data is cleared by restarting Servo, see
+                    // QServoBrowsingDataTypes::needs_restart
+                    if types.http_cache {
+                        self.browser.push_event(EmbedderEvent::ClearCache);
+                    }
+                    self.queue_qt(move |webview| webview.browsing_data_cleared_with(types));
+                }
+                QServoMessage::Cookie(request) => {
+                    let page = self.browser.webview_id().zip(self.browser.document_url());
+                    match page {
+                        Some((webview_id, url)) => match request.into_event(webview_id, url) {
+                            Ok(event) => self.browser.push_event(event),
+                            Err(err) => println!("failed to send cookie request: {err}"),
+                        },
+                        None => request.fail("There is no page"),
+                    }
+                }
+                QServoMessage::RunJavaScript(id, script) => {
+                    let result = self
+                        .browser
+                        .webview_id()
+                        .ok_or_else(|| "There is no page".to_owned())
+                        .and_then(|webview_id| {
+                            run_javascript(webview_id, id, script, self.qt_thread.clone())
+                        });
+                    match result {
+                        Ok(event) => self.browser.push_event(event),
+                        Err(err) => {
+                            self.queue_qt(move |webview| webview.javascript_result(id, Err(err)));
+                        }
+                    }
+                }
+                QServoMessage::ScreenGeometry(screen_geometry) => {
+                    self.servo.window().set_screen_geometry(screen_geometry);
+                }
+                QServoMessage::Settings(settings) => {
+                    settings.apply();
+                }
                 QServoMessage::Url(url) => {
                     // Don't update the url if this was the last url
                     if Some(&url) == current_url.as_ref() {
@@ -142,17 +412,51 @@ impl QServoThread {
                             .push_event(EmbedderEvent::NewWebView(url, self.browser_id));
                     }
                 }
+                QServoMessage::LoadContent(content) => {
+                    // Content is always loaded, even if it is the same as the current content
+                    // and the base URL is only current once the content has loaded, so that a
+                    // url set to the base URL meanwhile is still loaded
+                    current_url = None;
+                    let url = self.browser.load_content(content);
+
+                    if let Some(webview_id) = self.browser.webview_id() {
+                        self.browser
+                            .push_event(EmbedderEvent::LoadUrl(webview_id, url));
+                    } else {
+                        self.browser
+                            .push_event(EmbedderEvent::NewWebView(url, self.browser_id));
+                    }
+                }
+                QServoMessage::CannedResponses(responses) => {
+                    self.browser.set_canned_responses(responses);
+                }
+                QServoMessage::UrlSchemeHandlers(handlers) => {
+                    self.browser.set_url_scheme_handlers(handlers);
+                }
                 QServoMessage::BorrowSurface(sender, receiver) => {
                     let surfman = self.servo.window().rendering_context();
-                    let swap_chain = surfman.swap_chain().unwrap();
+                    let swap_chain = match surfman.swap_chain() {
+                        Ok(swap_chain) => swap_chain,
+                        Err(err) => {
+                            println!("failed to get swap chain: {err:?}");
+                            let _ = sender.send(None);
+                            continue;
+                        }
+                    };
 
                     let surface = swap_chain.take_surface();
 
                     println!("sending surface: {}", surface.is_some());
-                    sender.send(surface).unwrap();
+                    // If the renderer has gone then the surface is returned to us
+                    if let Err(SendError(surface)) = sender.send(surface) {
+                        if let Some(surface) = surface {
+                            swap_chain.recycle_surface(surface);
+                        }
+                        continue;
+                    }
 
                     println!("waiting for surface return");
-                    let surface = receiver.recv().unwrap();
+                    let surface = receiver.recv().ok().flatten();
 
                     println!("returned surface, recycling");
                     if let Some(surface) = surface {
@@ -183,6 +487,15 @@ impl QServoThread {
                                 current_url = Some(ServoUrl::from_url(url.to_owned()));
                             }
 
+                            for pipeline_id in response.pipelines.drain(..) {
+                                self.audio.add_pipeline(pipeline_id);
+                            }
+
+                            // A new web view is needed for the next url
+                            if response.webview_closed {
+                                current_url = None;
+                            }
+
                             // Store the favicon for the current url
                             if let Some(favicon) = response.favicon_url.as_ref() {
                                 if let Some(current_url) = current_url.as_ref() {
@@ -201,33 +514,97 @@ impl QServoThread {
                             }
 
                             // Handle the responses from browser events to Qt
-                            self.qt_thread
-                                .queue(move |mut webview| {
-                                    if let Some(title) = response.title {
-                                        webview.as_mut().set_title(QString::from(&title));
-                                    }
-                                    if let Some(loading) = response.loading {
-                                        webview.as_mut().set_loading(loading);
-                                    }
-                                    if let Some(favicon_url) = response.favicon_url {
-                                        webview.as_mut().set_favicon_url(QUrl::from(&favicon_url));
-                                    }
-                                    if let Some(url) = response.url {
-                                        webview.as_mut().set_url(QUrl::from(&url));
-                                    }
-                                    if let Some(url) = response.blocked_navigation_request {
-                                        webview
-                                            .as_mut()
-                                            .blocked_navigation_request(QUrl::from(&url));
-                                    }
-                                    if let Some(can_go_back) = response.can_go_back {
-                                        webview.as_mut().set_can_go_back(can_go_back);
+                            self.queue_qt(move |mut webview| {
+                                if let Some(title) = response.title {
+                                    webview.as_mut().set_title(QString::from(&title));
+                                }
+                                if let Some(status_text) = response.status_text {
+                                    let status_text = status_text.unwrap_or_default();
+                                    // Servo reports the link under the pointer as the status
+                                    let hovered_url = Url::parse(&status_text)
+                                        .map(|url| QUrl::from(&url))
+                                        .unwrap_or_default();
+                                    webview.as_mut().set_hovered_url(hovered_url);
+                                    webview
+                                        .as_mut()
+                                        .set_status_text(QString::from(&status_text));
+                                }
+                                if let Some(load_progress) = response.load_progress {
+                                    webview.as_mut().set_load_progress(load_progress);
+                                }
+                                if let Some(loading) = response.loading {
+                                    // A new load replaces a crashed page
+                                    if loading {
+                                        webview.as_mut().rust_mut().terminated = false;
                                     }
-                                    if let Some(can_go_forward) = response.can_go_forward {
-                                        webview.as_mut().set_can_go_forward(can_go_forward);
+                                    webview.as_mut().set_loading(loading);
+                                }
+                                for request in response.load_requests {
+                                    webview.as_mut().load_request_changed_with(&request);
+                                }
+                                if let Some(favicon_url) = response.favicon_url {
+                                    webview.as_mut().set_favicon_url(QUrl::from(&favicon_url));
+                                }
+                                if let Some(url) = response.url {
+                                    webview.as_mut().set_url(QUrl::from(&url));
+                                }
+                                if let Some(url) = response.blocked_navigation_request {
+                                    webview
+                                        .as_mut()
+                                        .blocked_navigation_request(QUrl::from(&url));
+                                }
+                                if let Some(can_go_back) = response.can_go_back {
+                                    webview.as_mut().set_can_go_back(can_go_back);
+                                }
+                                if let Some(can_go_forward) = response.can_go_forward {
+                                    webview.as_mut().set_can_go_forward(can_go_forward);
+                                }
+                                if let Some(full_screen) = response.full_screen {
+                                    webview.as_mut().request_full_screen(full_screen);
+                                }
+                                for event in response.media_session_events {
+                                    if let Some(media_session) =
+                                        webview.as_mut().media_session_mut()
+                                    {
+                                        media_session.handle_event(event);
                                     }
-                                })
-                                .unwrap();
+                                }
+                                webview.as_mut().update_recently_audible();
+                                for request in response.geometry_requests {
+                                    webview.as_mut().request_geometry_change(request);
+                                }
+                                for request in response.before_unload_requests {
+                                    webview.as_mut().request_before_unload(request);
+                                }
+                                for request in response.authentication_requests {
+                                    webview.as_mut().request_authentication(request);
+                                }
+                                for request in response.file_selection_requests {
+                                    webview.as_mut().request_file_selection(request);
+                                }
+                                for request in response.permission_requests {
+                                    webview.as_mut().request_permission(request);
+                                }
+                                for (origin, json) in response.messages {
+                                    webview.as_mut().receive_message(&origin, &json);
+                                }
+                                for request in response.web_resource_requests {
+                                    webview.as_mut().request_web_resource(request);
+                                }
+                                for request in response.javascript_dialogs {
+                                    webview.as_mut().request_javascript_dialog(request);
+                                }
+                                if let Some(termination) = response.terminated {
+                                    webview.as_mut().render_process_terminated_with(
+                                        &termination.reason,
+                                        &termination.backtrace,
+                                        termination.keep_frame,
+                                    );
+                                }
+                                if response.webview_closed {
+                                    webview.as_mut().webview_closed();
+                                }
+                            });
 
                             // Present when required
                             need_present |= response.present.unwrap_or(false);
@@ -271,15 +648,49 @@ impl QServoThread {
                     }
 
                     // Indicate that we have completed the heartbeat
-                    sender.send(()).unwrap();
+                    // The renderer could have gone while we were busy
+                    let _ = sender.send(());
 
                     println!("heartbeat!");
                 }
-                QServoMessage::Quit => break,
+                QServoMessage::Quit(sender) => {
+                    self.shutdown();
+                    let _ = sender.send(());
+                    return;
+                }
             }
         }
 
+        // The renderer has gone without asking us to quit
+        self.shutdown();
+    }
+
+    /// Shut Servo down, so that its state is persisted before the thread ends
+    ///
+    /// TODO: Servo has no blocking shutdown, so pump its events until it says that
+    /// it has shut down like servoshell does
+    fn shutdown(self) {
         println!("quiting!");
-        self.servo.handle_events(vec![EmbedderEvent::Quit]);
+        let mut servo = self.servo;
+        servo.handle_events(vec![EmbedderEvent::Quit]);
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        loop {
+            let shut_down = servo
+                .get_events()
+                .any(|(_, msg)| matches!(msg, EmbedderMsg::Shutdown));
+            if shut_down {
+                break;
+            }
+            if Instant::now() > deadline {
+                println!("Servo did not shut down in time");
+                break;
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+            servo.handle_events(vec![]);
+        }
+
+        servo.deinit();
     }
 }
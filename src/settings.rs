@@ -0,0 +1,370 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servosettings")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("cxx-qt-lib/qvariant.h");
+        type QVariant = cxx_qt_lib::QVariant;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_settings_unique_ptr() -> UniquePtr<ServoSettings>;
+
+        #[cxx_name = "qvariantFromJson"]
+        fn qvariant_from_json(json: &str) -> QVariant;
+
+        #[cxx_name = "qvariantToJson"]
+        fn qvariant_to_json(value: &QVariant) -> String;
+    }
+
+    unsafe extern "RustQt" {
+        /// The Servo preferences of a web view
+        ///
+        /// Servo's preferences are global to the process, so once a web view gives its
+        /// settings to Servo they apply to every web view, and the settings of the other
+        /// web views do not see the change.
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(bool, gamepad_enabled)]
+        #[qproperty(i32, layout_threads)]
+        #[qproperty(bool, webgl2_enabled)]
+        #[qproperty(bool, webgpu_enabled)]
+        #[qproperty(bool, webxr_enabled)]
+        type ServoSettings = super::QServoSettingsRust;
+
+        /// Set a Servo preference by its key, such as dom.webgpu.enabled
+        ///
+        /// Returns false if the key is unknown or the value is of the wrong type.
+        #[qinvokable]
+        fn set_pref(self: Pin<&mut ServoSettings>, key: &QString, value: &QVariant) -> bool;
+
+        /// The value of a Servo preference, or an invalid value if the key is unknown
+        #[qinvokable]
+        fn pref(self: &ServoSettings, key: &QString) -> QVariant;
+
+        /// The settings changed
+        #[qsignal]
+        fn changed(self: Pin<&mut ServoSettings>);
+    }
+
+    impl cxx_qt::Constructor<()> for ServoSettings {}
+}
+
+use core::pin::Pin;
+use std::{collections::BTreeMap, fmt};
+
+use cxx_qt::{CxxQtThread, CxxQtType};
+use cxx_qt_lib::{QString, QVariant};
+use serde_json::{json, Value};
+use servo::servo_config::{pref_util::PrefValue, prefs::pref_map};
+
+use crate::webview::qobject::ServoWebView;
+
+const GAMEPAD_ENABLED: &str = "dom.gamepad.enabled";
+const LAYOUT_THREADS: &str = "layout.threads";
+const WEBGL2_ENABLED: &str = "dom.webgl2.enabled";
+const WEBGPU_ENABLED: &str = "dom.webgpu.enabled";
+const WEBXR_ENABLED: &str = "dom.webxr.enabled";
+
+/// Why a preference could not be set
+#[derive(Debug)]
+pub enum QServoSettingsError {
+    /// Servo has no preference with the key
+    UnknownPref(String),
+    /// The value is not of the type of the preference
+    WrongType { key: String, value: PrefValue },
+}
+
+impl fmt::Display for QServoSettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPref(key) => write!(f, "unknown Servo preference: {key}"),
+            Self::WrongType { key, value } => {
+                write!(f, "wrong type for Servo preference {key}: {value:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for QServoSettingsError {}
+
+/// Servo preferences which differ from the defaults
+///
+/// Servo's preferences are shared by the whole process, so these apply to every web
+/// view once applied, and many of them are only read when Servo starts, so changes to
+/// those take effect after a restart.
+///
+/// TODO: Servo has no preferences for disabling JavaScript or media autoplay yet
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QServoSettings {
+    prefs: BTreeMap<String, PrefValue>,
+}
+
+impl QServoSettings {
+    /// Set a preference, the key must be known to Servo and the value of its type
+    pub fn set(
+        &mut self,
+        key: &str,
+        value: impl Into<PrefValue>,
+    ) -> Result<(), QServoSettingsError> {
+        let value = value.into();
+        let current = pref_map().get(key);
+        match (&current, &value) {
+            (PrefValue::Missing, _) => Err(QServoSettingsError::UnknownPref(key.to_owned())),
+            (PrefValue::Bool(_), PrefValue::Bool(_))
+            | (PrefValue::Int(_), PrefValue::Int(_))
+            | (PrefValue::Float(_), PrefValue::Float(_))
+            | (PrefValue::Str(_), PrefValue::Str(_))
+            | (PrefValue::Array(_), PrefValue::Array(_)) => {
+                self.prefs.insert(key.to_owned(), value);
+                Ok(())
+            }
+            // Whole numbers from QML arrive as integers
+            (PrefValue::Float(_), PrefValue::Int(value)) => {
+                self.prefs
+                    .insert(key.to_owned(), PrefValue::Float(*value as f64));
+                Ok(())
+            }
+            _ => Err(QServoSettingsError::WrongType {
+                key: key.to_owned(),
+                value,
+            }),
+        }
+    }
+
+    /// The value of a preference, which is Servo's default unless it has been set
+    pub fn get(&self, key: &str) -> PrefValue {
+        self.prefs
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| pref_map().get(key))
+    }
+
+    fn get_bool(&self, key: &str) -> bool {
+        matches!(self.get(key), PrefValue::Bool(true))
+    }
+
+    pub fn gamepad_enabled(&self) -> bool {
+        self.get_bool(GAMEPAD_ENABLED)
+    }
+
+    pub fn layout_threads(&self) -> i64 {
+        match self.get(LAYOUT_THREADS) {
+            PrefValue::Int(threads) => threads,
+            _ => 0,
+        }
+    }
+
+    pub fn webgl2_enabled(&self) -> bool {
+        self.get_bool(WEBGL2_ENABLED)
+    }
+
+    pub fn webgpu_enabled(&self) -> bool {
+        self.get_bool(WEBGPU_ENABLED)
+    }
+
+    pub fn webxr_enabled(&self) -> bool {
+        self.get_bool(WEBXR_ENABLED)
+    }
+
+    /// Give the preferences to Servo, for every web view of the process
+    pub(crate) fn apply(&self) {
+        for (key, value) in &self.prefs {
+            if let Err(err) = pref_map().set(key, value.clone()) {
+                println!("failed to set Servo preference {key}: {err:?}");
+            }
+        }
+    }
+}
+
+fn pref_from_json(value: Value) -> Option<PrefValue> {
+    match value {
+        Value::Bool(value) => Some(PrefValue::Bool(value)),
+        Value::Number(value) => value
+            .as_i64()
+            .map(PrefValue::Int)
+            .or_else(|| value.as_f64().map(PrefValue::Float)),
+        Value::String(value) => Some(PrefValue::Str(value)),
+        Value::Array(values) => values
+            .into_iter()
+            .map(pref_from_json)
+            .collect::<Option<Vec<_>>>()
+            .map(PrefValue::Array),
+        Value::Null | Value::Object(_) => None,
+    }
+}
+
+fn pref_to_json(value: PrefValue) -> Value {
+    match value {
+        PrefValue::Bool(value) => json!(value),
+        PrefValue::Int(value) => json!(value),
+        PrefValue::Float(value) => json!(value),
+        PrefValue::Str(value) => json!(value),
+        PrefValue::Array(values) => Value::Array(values.into_iter().map(pref_to_json).collect()),
+        PrefValue::Missing => Value::Null,
+    }
+}
+
+pub struct QServoSettingsRust {
+    gamepad_enabled: bool,
+    layout_threads: i32,
+    webgl2_enabled: bool,
+    webgpu_enabled: bool,
+    webxr_enabled: bool,
+    settings: QServoSettings,
+    qt_thread: Option<CxxQtThread<ServoWebView>>,
+}
+
+impl Default for QServoSettingsRust {
+    fn default() -> Self {
+        let settings = QServoSettings::default();
+        Self {
+            gamepad_enabled: settings.gamepad_enabled(),
+            layout_threads: settings.layout_threads() as i32,
+            webgl2_enabled: settings.webgl2_enabled(),
+            webgpu_enabled: settings.webgpu_enabled(),
+            webxr_enabled: settings.webxr_enabled(),
+            settings,
+            qt_thread: None,
+        }
+    }
+}
+
+impl qobject::ServoSettings {
+    /// Create the settings of a web view, changes are sent through the web view
+    pub(crate) fn new(qt_thread: CxxQtThread<ServoWebView>) -> cxx::UniquePtr<Self> {
+        let mut settings = qobject::servo_settings_unique_ptr();
+        settings.pin_mut().rust_mut().qt_thread = Some(qt_thread);
+        settings
+    }
+
+    /// The preferences which differ from Servo's defaults
+    pub fn settings(&self) -> &QServoSettings {
+        &self.settings
+    }
+
+    /// Set a Servo preference by its key, such as dom.webgpu.enabled
+    pub fn try_set_pref(
+        mut self: Pin<&mut Self>,
+        key: &str,
+        value: impl Into<PrefValue>,
+    ) -> Result<(), QServoSettingsError> {
+        self.as_mut().rust_mut().settings.set(key, value)?;
+        self.as_mut().sync_properties();
+        self.as_mut().settings_changed();
+        Ok(())
+    }
+
+    fn set_pref(self: Pin<&mut Self>, key: &QString, value: &QVariant) -> bool {
+        let key = key.to_string();
+        let value = serde_json::from_str(&qobject::qvariant_to_json(value))
+            .ok()
+            .and_then(pref_from_json);
+        let result = match value {
+            Some(value) => self.try_set_pref(&key, value),
+            None => Err(QServoSettingsError::WrongType {
+                key,
+                value: PrefValue::Missing,
+            }),
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                println!("{err}");
+                false
+            }
+        }
+    }
+
+    fn pref(&self, key: &QString) -> QVariant {
+        match self.settings.get(&key.to_string()) {
+            PrefValue::Missing => QVariant::default(),
+            value => qobject::qvariant_from_json(&pref_to_json(value).to_string()),
+        }
+    }
+
+    /// Update the typed properties after a preference was set by its key
+    fn sync_properties(mut self: Pin<&mut Self>) {
+        let settings = self.settings.clone();
+        self.as_mut()
+            .set_gamepad_enabled(settings.gamepad_enabled());
+        self.as_mut()
+            .set_layout_threads(settings.layout_threads() as i32);
+        self.as_mut().set_webgl2_enabled(settings.webgl2_enabled());
+        self.as_mut().set_webgpu_enabled(settings.webgpu_enabled());
+        self.as_mut().set_webxr_enabled(settings.webxr_enabled());
+    }
+
+    /// A typed property changed, store it as a preference
+    fn property_changed(mut self: Pin<&mut Self>, key: &str, value: PrefValue) {
+        if self.settings.get(key) == value {
+            return;
+        }
+
+        if let Err(err) = self.as_mut().rust_mut().settings.set(key, value) {
+            println!("{err}");
+            return;
+        }
+        self.as_mut().settings_changed();
+    }
+
+    fn settings_changed(mut self: Pin<&mut Self>) {
+        self.as_mut().changed();
+
+        if let Some(qt_thread) = self.qt_thread.as_ref() {
+            let settings = self.settings.clone();
+            if qt_thread
+                .queue(move |webview| webview.apply_servo_settings(settings))
+                .is_err()
+            {
+                println!("failed to queue settings, the web view has gone");
+            }
+        }
+    }
+}
+
+impl cxx_qt::Initialize for qobject::ServoSettings {
+    fn initialize(mut self: Pin<&mut Self>) {
+        self.as_mut()
+            .on_gamepad_enabled_changed(|qobject| {
+                let value = PrefValue::Bool(*qobject.gamepad_enabled());
+                qobject.property_changed(GAMEPAD_ENABLED, value);
+            })
+            .release();
+
+        self.as_mut()
+            .on_layout_threads_changed(|qobject| {
+                let value = PrefValue::Int(*qobject.layout_threads() as i64);
+                qobject.property_changed(LAYOUT_THREADS, value);
+            })
+            .release();
+
+        self.as_mut()
+            .on_webgl2_enabled_changed(|qobject| {
+                let value = PrefValue::Bool(*qobject.webgl2_enabled());
+                qobject.property_changed(WEBGL2_ENABLED, value);
+            })
+            .release();
+
+        self.as_mut()
+            .on_webgpu_enabled_changed(|qobject| {
+                let value = PrefValue::Bool(*qobject.webgpu_enabled());
+                qobject.property_changed(WEBGPU_ENABLED, value);
+            })
+            .release();
+
+        self.on_webxr_enabled_changed(|qobject| {
+            let value = PrefValue::Bool(*qobject.webxr_enabled());
+            qobject.property_changed(WEBXR_ENABLED, value);
+        })
+        .release();
+    }
+}
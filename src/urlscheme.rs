@@ -0,0 +1,246 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servourlscheme")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qbytearray.h");
+        type QByteArray = cxx_qt_lib::QByteArray;
+
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("helpers.h");
+
+        #[cxx_name = "qresourceRead"]
+        fn qresource_read(path: &QString, data: &mut QByteArray) -> bool;
+
+        #[cxx_name = "qmimeTypeForFileName"]
+        fn qmime_type_for_file_name(path: &QString) -> QString;
+    }
+}
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use cxx_qt_lib::{QByteArray, QString};
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::webresourcerequest::{QServoWebResourceAction, QServoWebResourceResponse};
+
+/// The content for a URL of a custom scheme
+pub struct QServoUrlSchemeResponse {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// A callback which returns the content for a URL of a custom scheme, or None if not found
+pub type QServoUrlSchemeHandler = dyn Fn(&Url) -> Option<QServoUrlSchemeResponse> + Send + Sync;
+
+/// The Qt resource path of a URL under the prefix, the host and path of the URL are
+/// percent-decoded
+///
+/// Returns None if the URL would leave the prefix or is not UTF-8 once decoded.
+fn resource_path(prefix: &str, url: &Url) -> Option<String> {
+    let host = percent_decode_str(url.host_str().unwrap_or_default()).decode_utf8();
+    let path = percent_decode_str(url.path().trim_start_matches('/')).decode_utf8();
+    let (host, path) = (host.ok()?, path.ok()?);
+
+    // A decoded %2F could make a parent segment which the URL parser did not resolve
+    let segments = [host.as_ref(), path.as_ref()]
+        .into_iter()
+        .flat_map(|part| part.split('/'))
+        .collect::<Vec<_>>();
+    if segments.contains(&"..") {
+        return None;
+    }
+
+    let path = std::iter::once(prefix)
+        .chain(segments)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(format!(":/{path}"))
+}
+
+/// A handler which serves the URLs of a scheme from Qt resources under the given prefix
+///
+/// For example with the prefix qrc:/ui the URL app://host/index.html is read from
+/// :/ui/host/index.html
+pub(crate) fn resource_prefix_handler(prefix: &str) -> Arc<QServoUrlSchemeHandler> {
+    let prefix = prefix
+        .strip_prefix("qrc")
+        .unwrap_or(prefix)
+        .trim_start_matches(':')
+        .trim_matches('/')
+        .to_owned();
+
+    Arc::new(move |url: &Url| {
+        let path = QString::from(&resource_path(&prefix, url)?);
+
+        let mut data = QByteArray::default();
+        if !qobject::qresource_read(&path, &mut data) {
+            return None;
+        }
+
+        Some(QServoUrlSchemeResponse {
+            mime_type: qobject::qmime_type_for_file_name(&path).to_string(),
+            data: data.as_slice().to_vec(),
+        })
+    })
+}
+
+/// The registered scheme handlers
+///
+/// Clones share the same handlers, so that handlers registered from Qt reach the
+/// Servo thread.
+///
+/// Requests of a registered scheme are answered when Servo asks whether to intercept
+/// them, before Servo looks at the scheme, so they never reach the network.
+///
+/// TODO: Servo gives URLs of a custom scheme an opaque origin and does not tell us
+/// which page a request is for, so a page of any origin can load the resources as
+/// subresources.
+#[derive(Clone, Default)]
+pub(crate) struct QServoUrlSchemeHandlers {
+    handlers: Arc<Mutex<HashMap<String, Arc<QServoUrlSchemeHandler>>>>,
+}
+
+impl QServoUrlSchemeHandlers {
+    pub(crate) fn register(&self, scheme: &str, handler: Arc<QServoUrlSchemeHandler>) {
+        self.handlers
+            .lock()
+            .unwrap()
+            .insert(scheme.to_ascii_lowercase(), handler);
+    }
+
+    fn handler(&self, scheme: &str) -> Option<Arc<QServoUrlSchemeHandler>> {
+        self.handlers.lock().unwrap().get(scheme).cloned()
+    }
+
+    /// The answer to a request if the scheme of its URL is registered
+    ///
+    /// The handler is called on the Servo thread.
+    pub(crate) fn action_for(&self, method: &str, url: &Url) -> Option<QServoWebResourceAction> {
+        let handler = self.handler(url.scheme())?;
+        if method != "GET" && method != "HEAD" {
+            return Some(QServoWebResourceAction::Block);
+        }
+
+        let response = match handler(url) {
+            Some(response) => QServoWebResourceResponse {
+                status_code: 200,
+                headers: vec![("Content-Type".to_owned(), response.mime_type)],
+                body: if method == "GET" {
+                    response.data
+                } else {
+                    vec![]
+                },
+            },
+            None => QServoWebResourceResponse {
+                status_code: 404,
+                headers: vec![],
+                body: vec![],
+            },
+        };
+        Some(QServoWebResourceAction::Respond(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handlers() -> QServoUrlSchemeHandlers {
+        let handlers = QServoUrlSchemeHandlers::default();
+        handlers.register(
+            "APP",
+            Arc::new(|url: &Url| {
+                (url.host_str() == Some("ui") && url.path() == "/index.html").then(|| {
+                    QServoUrlSchemeResponse {
+                        mime_type: "text/html".to_owned(),
+                        data: b"<p>hello</p>".to_vec(),
+                    }
+                })
+            }),
+        );
+        handlers
+    }
+
+    fn response(action: Option<QServoWebResourceAction>) -> QServoWebResourceResponse {
+        match action {
+            Some(QServoWebResourceAction::Respond(response)) => response,
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn action_for_unregistered_scheme() {
+        let url = Url::parse("https://ui/index.html").unwrap();
+        assert!(handlers().action_for("GET", &url).is_none());
+    }
+
+    #[test]
+    fn action_for_found() {
+        let url = Url::parse("app://ui/index.html").unwrap();
+        let response = response(handlers().action_for("GET", &url));
+        assert_eq!(response.status_code, 200);
+        assert_eq!(
+            response.headers,
+            vec![("Content-Type".to_owned(), "text/html".to_owned())]
+        );
+        assert_eq!(response.body, b"<p>hello</p>");
+    }
+
+    #[test]
+    fn action_for_head() {
+        let url = Url::parse("app://ui/index.html").unwrap();
+        let response = response(handlers().action_for("HEAD", &url));
+        assert_eq!(response.status_code, 200);
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn action_for_not_found() {
+        let url = Url::parse("app://ui/missing.html").unwrap();
+        let response = response(handlers().action_for("GET", &url));
+        assert_eq!(response.status_code, 404);
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn resource_path_joins_prefix_host_and_path() {
+        let url = Url::parse("app://host/dir/index.html").unwrap();
+        assert_eq!(resource_path("ui", &url).as_deref(), Some(":/ui/host/dir/index.html"));
+        assert_eq!(resource_path("", &url).as_deref(), Some(":/host/dir/index.html"));
+    }
+
+    #[test]
+    fn resource_path_percent_decoded() {
+        let url = Url::parse("app://host/my file/caf\u{e9}.html").unwrap();
+        assert_eq!(
+            resource_path("ui", &url).as_deref(),
+            Some(":/ui/host/my file/caf\u{e9}.html")
+        );
+    }
+
+    #[test]
+    fn resource_path_parent() {
+        let url = Url::parse("app://host/..%2F..%2Fsecret.txt").unwrap();
+        assert_eq!(resource_path("ui", &url), None);
+    }
+
+    #[test]
+    fn action_for_post() {
+        let url = Url::parse("app://ui/index.html").unwrap();
+        assert!(matches!(
+            handlers().action_for("POST", &url),
+            Some(QServoWebResourceAction::Block)
+        ));
+    }
+}
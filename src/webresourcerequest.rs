@@ -0,0 +1,398 @@
+// SPDX-FileCopyrightText: 2024 Klarälvdalens Datakonsult AB, a KDAB Group company <info@kdab.com>
+// SPDX-FileContributor: Andrew Hayzen <andrew.hayzen@kdab.com>
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#[cxx_qt::bridge(cxx_file_stem = "servowebresourcerequest")]
+pub(crate) mod qobject {
+    unsafe extern "C++" {
+        include!("cxx-qt-lib/qbytearray.h");
+        type QByteArray = cxx_qt_lib::QByteArray;
+
+        include!("cxx-qt-lib/qstring.h");
+        type QString = cxx_qt_lib::QString;
+
+        include!("cxx-qt-lib/qurl.h");
+        type QUrl = cxx_qt_lib::QUrl;
+
+        include!("helpers.h");
+        #[cxx_name = "constructUniquePtr"]
+        fn servo_web_resource_request_unique_ptr() -> UniquePtr<ServoWebResourceRequest>;
+    }
+
+    unsafe extern "RustQt" {
+        #[qobject]
+        #[qml_element]
+        #[qml_uncreatable]
+        #[qproperty(bool, is_main_frame)]
+        #[qproperty(QString, method)]
+        #[qproperty(QUrl, url)]
+        type ServoWebResourceRequest = super::QServoWebResourceRequestRust;
+
+        /// Let the request go to the network
+        #[qinvokable]
+        fn allow(self: Pin<&mut ServoWebResourceRequest>);
+
+        /// Fail the request with a network error
+        #[qinvokable]
+        fn block(self: Pin<&mut ServoWebResourceRequest>);
+
+        /// Redirect the request to another URL
+        #[qinvokable]
+        fn redirect(self: Pin<&mut ServoWebResourceRequest>, url: &QUrl);
+
+        /// Answer the request with a response instead of the network
+        #[qinvokable]
+        fn respond(
+            self: Pin<&mut ServoWebResourceRequest>,
+            status_code: i32,
+            content_type: &QString,
+            body: &QByteArray,
+        );
+    }
+
+    impl cxx_qt::Constructor<()> for ServoWebResourceRequest {}
+}
+
+use core::pin::Pin;
+use cxx_qt::CxxQtType;
+use cxx_qt_lib::{QByteArray, QString, QUrl};
+use http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use servo::{
+    embedder_traits::{
+        HttpBodyData, WebResourceRequest, WebResourceResponse, WebResourceResponseMsg,
+    },
+    ipc_channel::ipc::IpcSender,
+    servo_url::ServoUrl,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+/// A request for a web resource, such as a navigation or a subresource of the page
+pub struct QServoWebResourceRequest {
+    pub method: String,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub is_main_frame: bool,
+    pub is_redirect: bool,
+}
+
+/// A synthetic response to a web resource request
+#[derive(Clone)]
+pub struct QServoWebResourceResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// What to do with a web resource request
+pub enum QServoWebResourceAction {
+    Allow,
+    Block,
+    Redirect(Url),
+    Respond(QServoWebResourceResponse),
+}
+
+/// Decides what happens to web resource requests, called on the Servo thread
+///
+/// Requests which are allowed are given to QML if there is a handler. The headers of a
+/// request cannot be changed, Servo only lets us answer it.
+pub trait QServoRequestInterceptor: Send + Sync {
+    fn intercept(&self, request: &QServoWebResourceRequest) -> QServoWebResourceAction;
+}
+
+/// Canned responses for requests of the given URLs
+///
+/// Clones share the same responses, so responses added from Qt reach the Servo thread.
+/// They are looked at before the request interceptor, so they do not replace it.
+#[derive(Clone, Default)]
+pub(crate) struct QServoCannedResponses {
+    responses: Arc<Mutex<HashMap<Url, QServoWebResourceResponse>>>,
+}
+
+impl QServoCannedResponses {
+    pub(crate) fn insert(&self, mut url: Url, response: QServoWebResourceResponse) {
+        // The fragment is not part of a request
+        url.set_fragment(None);
+        self.responses.lock().unwrap().insert(url, response);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.responses.lock().unwrap().clear();
+    }
+}
+
+
+    /// The answer to a request of the URL if there is a canned response for it
+    pub(crate) fn action_for(&self, url: &Url) -> Option<QServoWebResourceAction> {
+        self.responses
+            .lock()
+            .unwrap()
+            .get(url)
+            .cloned()
+            .map(QServoWebResourceAction::Respond)
+    }
+}
+
+fn header_map(headers: &[(String, String)]) -> HeaderMap {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                header_map.append(name, value);
+            }
+            _ => println!("ignoring invalid header: {name}"),
+        }
+    }
+    header_map
+}
+
+/// The answer channel of a web resource request
+struct QServoWebResourceResponder {
+    url: ServoUrl,
+    sender: IpcSender<WebResourceResponseMsg>,
+}
+
+impl QServoWebResourceResponder {
+    fn send(&self, message: WebResourceResponseMsg) {
+        if let Err(err) = self.sender.send(message) {
+            println!("failed to answer web resource request: {err:?}");
+        }
+    }
+
+    fn send_response(&self, status_code: StatusCode, headers: HeaderMap, body: Vec<u8>) {
+        let response = WebResourceResponse::new(self.url.clone())
+            .headers(headers)
+            .status_code(status_code)
+            .status_message(
+                status_code
+                    .canonical_reason()
+                    .unwrap_or_default()
+                    .as_bytes()
+                    .to_vec(),
+            );
+        self.send(WebResourceResponseMsg::Start(response));
+        if !body.is_empty() {
+            self.send(WebResourceResponseMsg::Body(HttpBodyData::Chunk(body)));
+        }
+        self.send(WebResourceResponseMsg::Body(HttpBodyData::Done));
+    }
+
+    fn respond(self, action: QServoWebResourceAction) {
+        match action {
+            QServoWebResourceAction::Allow => self.send(WebResourceResponseMsg::None),
+            QServoWebResourceAction::Block => {
+                // Cancelling the body turns the response into a network error
+                self.send(WebResourceResponseMsg::Start(WebResourceResponse::new(
+                    self.url.clone(),
+                )));
+                self.send(WebResourceResponseMsg::Body(HttpBodyData::Cancelled));
+            }
+            QServoWebResourceAction::Redirect(url) => {
+                let mut headers = HeaderMap::new();
+                if let Ok(location) = HeaderValue::from_str(url.as_str()) {
+                    headers.insert(header::LOCATION, location);
+                }
+                self.send_response(StatusCode::FOUND, headers, vec![]);
+            }
+            QServoWebResourceAction::Respond(response) => {
+                let status_code =
+                    StatusCode::from_u16(response.status_code).unwrap_or(StatusCode::OK);
+                self.send_response(status_code, header_map(&response.headers), response.body);
+            }
+        }
+    }
+}
+
+/// A web resource request which has not been answered yet
+pub(crate) struct QServoWebResourceRequestPending {
+    request: QServoWebResourceRequest,
+    responder: QServoWebResourceResponder,
+}
+
+impl QServoWebResourceRequestPending {
+    pub(crate) fn new(
+        request: WebResourceRequest,
+        sender: IpcSender<WebResourceResponseMsg>,
+    ) -> Self {
+        let headers = request
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_owned(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+
+        Self {
+            request: QServoWebResourceRequest {
+                method: request.method.as_str().to_owned(),
+                url: request.url.as_url().to_owned(),
+                headers,
+                is_main_frame: request.is_for_main_frame,
+                is_redirect: request.is_redirect,
+            },
+            responder: QServoWebResourceResponder {
+                url: request.url,
+                sender,
+            },
+        }
+    }
+
+    /// Ask the interceptor, returns the request if it was allowed through
+    pub(crate) fn intercept(self, interceptor: &dyn QServoRequestInterceptor) -> Option<Self> {
+        match interceptor.intercept(&self.request) {
+            QServoWebResourceAction::Allow => Some(self),
+            action => {
+                self.responder.respond(action);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn is_main_frame(&self) -> bool {
+        self.request.is_main_frame
+    }
+
+    pub(crate) fn method(&self) -> &str {
+        &self.request.method
+    }
+
+    pub(crate) fn url(&self) -> &Url {
+        &self.request.url
+    }
+
+    /// Answer the request without asking the interceptor or Qt
+    pub(crate) fn respond(self, action: QServoWebResourceAction) {
+        self.responder.respond(action);
+    }
+
+    /// Let the request go to the network, used when there is no handler in Qt
+    pub(crate) fn allow(self) {
+        self.responder.respond(QServoWebResourceAction::Allow);
+    }
+
+    pub(crate) fn into_qobject(self) -> cxx::UniquePtr<qobject::ServoWebResourceRequest> {
+        let mut request = qobject::servo_web_resource_request_unique_ptr();
+        request
+            .pin_mut()
+            .set_is_main_frame(self.request.is_main_frame);
+        request
+            .pin_mut()
+            .set_method(QString::from(&self.request.method));
+        request.pin_mut().set_url(QUrl::from(&self.request.url));
+        request.pin_mut().rust_mut().responder = Some(self.responder);
+        request
+    }
+}
+
+#[derive(Default)]
+pub struct QServoWebResourceRequestRust {
+    is_main_frame: bool,
+    method: QString,
+    url: QUrl,
+    responder: Option<QServoWebResourceResponder>,
+}
+
+impl Drop for QServoWebResourceRequestRust {
+    fn drop(&mut self) {
+        // Never leave the fetch waiting for an answer
+        if let Some(responder) = self.responder.take() {
+            responder.respond(QServoWebResourceAction::Allow);
+        }
+    }
+}
+
+impl qobject::ServoWebResourceRequest {
+    /// Whether the request has been answered and can be destroyed
+    pub(crate) fn is_answered(&self) -> bool {
+        self.responder.is_none()
+    }
+
+    fn respond_with(mut self: Pin<&mut Self>, action: QServoWebResourceAction) {
+        if let Some(responder) = self.as_mut().rust_mut().responder.take() {
+            responder.respond(action);
+        }
+    }
+
+    fn allow(self: Pin<&mut Self>) {
+        self.respond_with(QServoWebResourceAction::Allow);
+    }
+
+    fn block(self: Pin<&mut Self>) {
+        self.respond_with(QServoWebResourceAction::Block);
+    }
+
+    fn redirect(self: Pin<&mut Self>, url: &QUrl) {
+        match Url::try_from(url) {
+            Ok(url) => self.respond_with(QServoWebResourceAction::Redirect(url)),
+            Err(_) => self.respond_with(QServoWebResourceAction::Block),
+        }
+    }
+
+    fn respond(self: Pin<&mut Self>, status_code: i32, content_type: &QString, body: &QByteArray) {
+        let response = QServoWebResourceResponse {
+            status_code: u16::try_from(status_code).unwrap_or(200),
+            headers: vec![("Content-Type".to_owned(), content_type.to_string())],
+            body: body.as_slice().to_vec(),
+        };
+        self.respond_with(QServoWebResourceAction::Respond(response));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn canned_responses() -> QServoCannedResponses {
+        let canned_responses = QServoCannedResponses::default();
+        canned_responses.insert(
+            Url::parse("https://example.com/data.json#ignored").unwrap(),
+            QServoWebResourceResponse {
+                status_code: 200,
+                headers: vec![("Content-Type".to_owned(), "application/json".to_owned())],
+                body: b"{}".to_vec(),
+            },
+        );
+        canned_responses
+    }
+
+    #[test]
+    fn canned_response() {
+        match canned_responses().action_for(&url("https://example.com/data.json")) {
+            Some(QServoWebResourceAction::Respond(response)) => {
+                assert_eq!(response.status_code, 200);
+                assert_eq!(response.body, b"{}");
+            }
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn canned_response_other_url() {
+        assert!(canned_responses()
+            .action_for(&url("https://example.com/other.json"))
+            .is_none());
+    }
+
+    #[test]
+    fn canned_responses_cleared() {
+        let canned_responses = canned_responses();
+        canned_responses.clear();
+        assert!(canned_responses
+            .action_for(&url("https://example.com/data.json"))
+            .is_none());
+    }
+}
@@ -6,15 +6,27 @@
 #[cxx_qt::bridge(cxx_file_stem = "servowebview")]
 pub(crate) mod qobject {
     unsafe extern "C++" {
+        include!("cxx-qt-lib/qbytearray.h");
+        type QByteArray = cxx_qt_lib::QByteArray;
+
+        include!("cxx-qt-lib/qrect.h");
+        type QRect = cxx_qt_lib::QRect;
+
         include!("cxx-qt-lib/qstring.h");
         type QString = cxx_qt_lib::QString;
 
         include!("cxx-qt-lib/qsizef.h");
         type QSizeF = cxx_qt_lib::QSizeF;
 
+        include!("cxx-qt-lib/qstringlist.h");
+        type QStringList = cxx_qt_lib::QStringList;
+
         include!("cxx-qt-lib/qurl.h");
         type QUrl = cxx_qt_lib::QUrl;
 
+        include!("cxx-qt-lib/qvariant.h");
+        type QVariant = cxx_qt_lib::QVariant;
+
         include!(<QQuickFramebufferObject>);
 
         include!("helpers.h");
@@ -23,6 +35,75 @@ pub(crate) mod qobject {
 
         #[cxx_name = "setMirrorVertically"]
         fn set_mirror_vertically(self: Pin<&mut ServoWebView>, enable: bool);
+
+        #[cxx_name = "qobjectIsSignalConnected"]
+        fn qobject_is_signal_connected(object: &ServoWebView, signature: &str) -> bool;
+
+        #[cxx_name = "qquickItemScreenGeometry"]
+        fn qquick_item_screen_geometry(item: &ServoWebView) -> QRect;
+
+        #[cxx_name = "qquickItemScreenAvailableGeometry"]
+        fn qquick_item_screen_available_geometry(item: &ServoWebView) -> QRect;
+
+        #[cxx_name = "qquickItemWindowGeometry"]
+        fn qquick_item_window_geometry(item: &ServoWebView) -> QRect;
+
+        include!(<QtQml/QJSValue>);
+        type QJSValue;
+
+        #[cxx_name = "constructUniquePtr"]
+        fn qjsvalue_unique_ptr(value: &QJSValue) -> UniquePtr<QJSValue>;
+
+        #[cxx_name = "qvariantFromJson"]
+        fn qvariant_from_json(json: &str) -> QVariant;
+
+        #[cxx_name = "qvariantToJson"]
+        fn qvariant_to_json(value: &QVariant) -> String;
+
+        #[cxx_name = "qjsvalueCallWithJson"]
+        fn qjsvalue_call_with_json(
+            context: &ServoWebView,
+            callback: Pin<&mut QJSValue>,
+            json: &str,
+        );
+
+        #[cxx_name = "qjsvalueCallWithError"]
+        fn qjsvalue_call_with_error(callback: Pin<&mut QJSValue>, error: &QString);
+    }
+
+    unsafe extern "C++" {
+        include!("cxx-qt-gen/servoauthenticationrequest.cxxqt.h");
+        type ServoAuthenticationRequest =
+            crate::authenticationrequest::qobject::ServoAuthenticationRequest;
+
+        include!("cxx-qt-gen/servobeforeunloadrequest.cxxqt.h");
+        type ServoBeforeUnloadRequest =
+            crate::beforeunloadrequest::qobject::ServoBeforeUnloadRequest;
+
+        include!("cxx-qt-gen/servofileselectionrequest.cxxqt.h");
+        type ServoFileSelectionRequest =
+            crate::fileselectionrequest::qobject::ServoFileSelectionRequest;
+
+        include!("cxx-qt-gen/servofullscreenrequest.cxxqt.h");
+        type ServoFullScreenRequest = crate::fullscreenrequest::qobject::ServoFullScreenRequest;
+
+        include!("cxx-qt-gen/servojavascriptdialog.cxxqt.h");
+        type ServoJavaScriptDialog = crate::javascriptdialog::qobject::ServoJavaScriptDialog;
+
+        include!("cxx-qt-gen/servomediasession.cxxqt.h");
+        type ServoMediaSession = crate::mediasession::qobject::ServoMediaSession;
+
+        include!("cxx-qt-gen/servoprofile.cxxqt.h");
+        type ServoProfile = crate::profile::qobject::ServoProfile;
+
+        include!("cxx-qt-gen/servosettings.cxxqt.h");
+        type ServoSettings = crate::settings::qobject::ServoSettings;
+
+        include!("cxx-qt-gen/servopermissionrequest.cxxqt.h");
+        type ServoPermissionRequest = crate::permissionrequest::qobject::ServoPermissionRequest;
+
+        include!("cxx-qt-gen/servowebresourcerequest.cxxqt.h");
+        type ServoWebResourceRequest = crate::webresourcerequest::qobject::ServoWebResourceRequest;
     }
 
     /// This enum specifies why the focus changed. It will be passed through QWidget::setFocus
@@ -53,17 +134,68 @@ pub(crate) mod qobject {
         type FocusReason;
     }
 
+    /// The status of a load reported by loadRequestChanged
+    #[qenum(ServoWebView)]
+    enum LoadStatus {
+        /// The load has started.
+        LoadStartedStatus,
+        /// The load has finished successfully.
+        LoadSucceededStatus,
+        /// The load has failed, see the error domain and string for why.
+        LoadFailedStatus,
+        /// The load was stopped with stop().
+        LoadStoppedStatus,
+    }
+
+    /// The kind of error which caused a load to fail
+    #[qenum(ServoWebView)]
+    enum ErrorDomain {
+        /// The load did not fail.
+        NoErrorDomain,
+        /// The content could not be interpreted or the engine failed.
+        InternalErrorDomain,
+        /// The host could not be reached or the connection was lost.
+        ConnectionErrorDomain,
+        /// The certificate of the host could not be verified.
+        CertificateErrorDomain,
+        /// The navigation was not allowed.
+        NavigationErrorDomain,
+    }
+
     unsafe extern "RustQt" {
+        /// A web view which shows a page rendered by Servo
+        ///
+        /// Servo only reads userAgent when it starts, so a change takes effect with restart().
+        ///
+        /// There is no httpAcceptLanguage and no way to add default request headers. Servo
+        /// always sends its own Accept-Language, en-US, and its web resource hook can only
+        /// answer a request, not change its headers.
+        ///
+        /// volume scales the volume of the audio and video elements of the page, from 0 to
+        /// 1. recentlyAudible is read only and follows the media session of the page.
         #[qobject]
         #[base = "QQuickFramebufferObject"]
         #[qml_element]
+        #[qproperty(QStringList, allowed_message_origins)]
+        #[qproperty(bool, audio_muted)]
         #[qproperty(bool, can_go_back)]
         #[qproperty(bool, can_go_forward)]
+        #[qproperty(QString, error_page_template)]
         #[qproperty(QUrl, favicon_url)]
+        #[qproperty(QUrl, hovered_url)]
+        #[qproperty(bool, is_full_screen)]
+        #[qproperty(i32, load_progress)]
         #[qproperty(bool, loading)]
+        #[qproperty(*mut ServoMediaSession, media_session, READ, CONSTANT)]
         #[qproperty(bool, navigation_allowed)]
+        #[qproperty(*mut ServoProfile, profile)]
+        #[qproperty(bool, recently_audible, READ, NOTIFY)]
+        #[qproperty(*mut ServoSettings, settings, READ, CONSTANT)]
+        #[qproperty(QString, status_text)]
         #[qproperty(QString, title)]
         #[qproperty(QUrl, url)]
+        #[qproperty(QString, user_agent)]
+        #[qproperty(f64, volume)]
         type ServoWebView = super::QServoWebViewRust;
 
         #[cxx_override]
@@ -88,14 +220,288 @@ pub(crate) mod qobject {
         #[inherit]
         fn update(self: Pin<&mut ServoWebView>);
 
+        #[qsignal]
+        unsafe fn authentication_required(
+            self: Pin<&mut ServoWebView>,
+            request: *mut ServoAuthenticationRequest,
+        );
+
+        /// The page has a beforeunload handler and asks whether it may be left
+        #[qsignal]
+        unsafe fn before_unload_requested(
+            self: Pin<&mut ServoWebView>,
+            request: *mut ServoBeforeUnloadRequest,
+        );
+
         #[qsignal]
         fn blocked_navigation_request(self: Pin<&mut ServoWebView>, blocked_url: QUrl);
 
+        #[qsignal]
+        unsafe fn file_selection_requested(
+            self: Pin<&mut ServoWebView>,
+            request: *mut ServoFileSelectionRequest,
+        );
+
+        /// The page asked for the window to be moved or resized with window.moveTo or
+        /// window.resizeTo, the host decides whether to honour this
+        #[qsignal]
+        fn geometry_change_requested(self: Pin<&mut ServoWebView>, geometry: QRect);
+
+        /// The page closed itself with window.close(), the item is now blank and a new
+        /// url can be loaded
+        #[qsignal]
+        fn window_close_requested(self: Pin<&mut ServoWebView>);
+
+        /// The page asked to enter or leave fullscreen, isFullScreen changes once the
+        /// request is answered and the page leaves fullscreen if it is rejected
+        #[qsignal]
+        unsafe fn full_screen_requested(
+            self: Pin<&mut ServoWebView>,
+            request: *mut ServoFullScreenRequest,
+        );
+
+        #[qsignal]
+        #[cxx_name = "javaScriptDialogRequested"]
+        unsafe fn javascript_dialog_requested(
+            self: Pin<&mut ServoWebView>,
+            dialog: *mut ServoJavaScriptDialog,
+        );
+
+        #[qsignal]
+        unsafe fn permission_requested(
+            self: Pin<&mut ServoWebView>,
+            request: *mut ServoPermissionRequest,
+        );
+
+        /// Servo or the content of the page crashed, the last frame is kept on screen
+        /// until the next load or restart() is called
+        ///
+        /// When the content crashed and there is an errorPageTemplate it is shown instead.
+        #[qsignal]
+        fn render_process_terminated(
+            self: Pin<&mut ServoWebView>,
+            reason: QString,
+            backtrace: QString,
+        );
+
+        /// A navigation or subresource is requested, this is only emitted when there is a
+        /// handler, otherwise it is allowed
+        ///
+        /// The request waits until it is answered, which can be after the handler returns.
+        #[qsignal]
+        unsafe fn web_resource_requested(
+            self: Pin<&mut ServoWebView>,
+            request: *mut ServoWebResourceRequest,
+        );
+
+        /// The browsing data of clearBrowsingData has been cleared
+        ///
+        /// Only the types which were cleared are given, which can be fewer than asked for.
+        #[qsignal]
+        fn browsing_data_cleared(self: Pin<&mut ServoWebView>, types: QStringList);
+
+        /// A cookie was added through setCookie or importCookies
+        ///
+        /// Cookies which pages set themselves are not signalled.
+        #[qsignal]
+        fn cookie_added(self: Pin<&mut ServoWebView>, cookie: QVariant);
+
+        /// A cookie was removed through deleteCookie or clearCookies
+        ///
+        /// Cookies which pages remove themselves are not signalled.
+        #[qsignal]
+        fn cookie_removed(self: Pin<&mut ServoWebView>, cookie: QVariant);
+
+        /// A message from window.qt.postMessage in a page of an allowed origin
+        ///
+        /// WARNING: Servo does not tell us which frame sent the message, so the origin is
+        /// of the page in the top level frame even when a frame of another origin sent it.
+        /// Only allow origins whose pages do not embed content which is not trusted.
+        #[qsignal]
+        fn message_received(self: Pin<&mut ServoWebView>, origin: QString, data: QVariant);
+
+        /// A load has started, succeeded, failed or was stopped
+        ///
+        /// The load request is an object with status, url, errorDomain, errorString and
+        /// httpStatusCode. The status code is only known if Servo gives it, otherwise it is
+        /// 0, and a page with an error status is a succeeded load of the page of the site.
+        #[qsignal]
+        fn load_request_changed(self: Pin<&mut ServoWebView>, load_request: QVariant);
+
+        #[qinvokable]
+        fn exit_full_screen(self: Pin<&mut ServoWebView>);
+
         #[qinvokable]
         fn go_back(self: Pin<&mut ServoWebView>);
 
         #[qinvokable]
         fn go_forward(self: Pin<&mut ServoWebView>);
+
+        /// Load the data with the mime type, url is reported as the base url
+        ///
+        /// Relative URLs of HTML are resolved against the base url.
+        #[qinvokable]
+        fn load_data(
+            self: Pin<&mut ServoWebView>,
+            data: &QByteArray,
+            mime_type: &QString,
+            base_url: &QUrl,
+        );
+
+        /// Load the HTML, relative URLs are resolved against the base url
+        #[qinvokable]
+        fn load_html(self: Pin<&mut ServoWebView>, html: &QString, base_url: &QUrl);
+
+        /// Clear browsing data, browsingDataCleared is emitted once it has been cleared
+        ///
+        /// The types can be httpCache, cookies, localStorage and sessionStorage, they are
+        /// cleared for every site. Servo only lets us clear the cookies and storage of the
+        /// current page, so for these Servo is restarted without the data it persisted and
+        /// the page is loaded again. Other web views using the same storagePath keep their
+        /// copy in memory and write it back when they shut down.
+        ///
+        /// Returns false if a type is unknown or not supported, such as indexedDB and
+        /// history, then nothing is cleared.
+        #[qinvokable]
+        #[cxx_name = "clearBrowsingData"]
+        fn clear_browsing_data_named(self: Pin<&mut ServoWebView>, types: &QStringList) -> bool;
+
+        /// Remove the cookies of the host which are sent to the current page, an empty
+        /// host removes all of those
+        ///
+        /// Servo only gives us the cookies of the current page, cookies of other pages
+        /// are kept.
+        #[qinvokable]
+        fn clear_cookies(self: Pin<&mut ServoWebView>, host: &QString);
+
+        /// Remove every credential given to storeCredentials
+        #[qinvokable]
+        fn clear_credentials(self: Pin<&mut ServoWebView>);
+
+        /// Forget the permission decisions of the origin of the url
+        #[qinvokable]
+        fn clear_permissions(self: Pin<&mut ServoWebView>, url: &QUrl);
+
+        /// Give the callback the cookies of the host which are sent to the current page,
+        /// an empty host gives all of those
+        ///
+        /// Each cookie is an object with name, value, domain, path, expires in
+        /// milliseconds since the epoch, secure and httpOnly properties.
+        #[qinvokable]
+        fn cookies(self: Pin<&mut ServoWebView>, host: &QString, callback: &QJSValue);
+
+        #[qinvokable]
+        fn delete_cookie(self: Pin<&mut ServoWebView>, name: &QString, host: &QString);
+
+        /// Give the callback the cookies of the host which are sent to the current page
+        /// in the Netscape cookie file format
+        #[qinvokable]
+        fn export_cookies(self: Pin<&mut ServoWebView>, host: &QString, callback: &QJSValue);
+
+        /// Add the cookies of a Netscape cookie file, returns how many were read
+        ///
+        /// Only the cookies for the host of the current page are added, see setCookie.
+        #[qinvokable]
+        fn import_cookies(self: Pin<&mut ServoWebView>, netscape: &QString) -> i32;
+
+        /// Answer authentication requests for documents of the origin of the url with
+        /// these credentials instead of asking QML, this is intended for automated tests
+        #[qinvokable]
+        fn store_credentials(
+            self: Pin<&mut ServoWebView>,
+            url: &QUrl,
+            user: &QString,
+            password: &QString,
+        );
+
+        /// Add a cookie, which is an object like those given by cookies
+        ///
+        /// Servo can only set cookies for the current page, so the domain has to be empty
+        /// or the host of the current page, with a leading dot for subdomains too. Other
+        /// cookies, and names, values or paths with a semicolon or control character, are
+        /// refused and cookieAdded is not emitted.
+        ///
+        /// Setting cookies before the first load is not supported, so they cannot be used
+        /// to sign in to the first page, for example with single sign-on.
+        ///
+        /// Returns false if the cookie has no name.
+        #[qinvokable]
+        fn set_cookie(self: Pin<&mut ServoWebView>, cookie: &QVariant) -> bool;
+
+        /// Answer permission requests for the feature, such as geolocation or camera, of
+        /// pages of the origin of the url with this decision instead of asking QML
+        #[qinvokable]
+        fn set_permission(
+            self: Pin<&mut ServoWebView>,
+            url: &QUrl,
+            feature: &QString,
+            granted: bool,
+        );
+
+        /// Dispatch a message event at window.qt, if the page is of an allowed origin
+        #[qinvokable]
+        fn post_message(self: Pin<&mut ServoWebView>, data: &QVariant);
+
+        /// Serve URLs of the scheme from Qt resources under the prefix, such as qrc:/ui
+        ///
+        /// Pages of the scheme have an opaque origin, so each is cross-origin to the others.
+        #[qinvokable]
+        fn register_url_scheme_resource_prefix(
+            self: Pin<&mut ServoWebView>,
+            scheme: &QString,
+            prefix: &QString,
+        );
+
+        /// Answer requests of the url with this response instead of the network
+        ///
+        /// This is intended for offline demos and tests. Returns false if the url is
+        /// invalid or the status code is not between 100 and 599.
+        #[qinvokable]
+        fn set_canned_response(
+            self: Pin<&mut ServoWebView>,
+            url: &QUrl,
+            status_code: i32,
+            content_type: &QString,
+            body: &QByteArray,
+        ) -> bool;
+
+        /// Forget every response given to setCannedResponse
+        #[qinvokable]
+        fn clear_canned_responses(self: Pin<&mut ServoWebView>);
+
+        #[qinvokable]
+        fn reload(self: Pin<&mut ServoWebView>);
+
+        #[qinvokable]
+        fn reload_bypassing_cache(self: Pin<&mut ServoWebView>);
+
+        /// Rebuild Servo and load the current url again
+        #[qinvokable]
+        fn restart(self: Pin<&mut ServoWebView>);
+
+        /// Answer every file selection with these files instead of asking QML
+        ///
+        /// An empty list asks QML again, this is intended for automated tests.
+        #[qinvokable]
+        fn set_file_chooser_result(self: Pin<&mut ServoWebView>, files: &QStringList);
+
+        /// Evaluate the script in the page, the callback is given the result
+        ///
+        /// If the script throws or cannot run then the result is undefined and the callback
+        /// is given the error message as a second argument.
+        #[qinvokable]
+        #[cxx_name = "runJavaScript"]
+        fn run_javascript(self: Pin<&mut ServoWebView>, script: &QString, callback: &QJSValue);
+
+        #[qinvokable]
+        fn stop(self: Pin<&mut ServoWebView>);
+
+        /// Close the page, windowCloseRequested is emitted once it has closed
+        ///
+        /// A page with a beforeunload handler is asked first with beforeUnloadRequested,
+        /// if that is cancelled the page stays open.
+        #[qinvokable]
+        fn try_close(self: Pin<&mut ServoWebView>);
     }
 
     unsafe extern "C++" {
@@ -209,18 +615,48 @@ pub(crate) mod qobject {
 }
 
 use core::pin::Pin;
-use cxx_qt::CxxQtType;
-use cxx_qt_lib::{QPointF, QString, QUrl};
-use euclid::Point2D;
+use cxx_qt::{CxxQtType, Threading};
+use cxx_qt_lib::{QByteArray, QList, QPointF, QRect, QString, QStringList, QUrl, QVariant};
+use euclid::{Point2D, Rect, Size2D};
 use qobject::{FocusReason, QEventPointState, QMouseEventButton};
 use servo::{
     compositing::windowing::{EmbedderEvent, MouseWindowEvent},
     keyboard_types::{Code, Key, KeyState, KeyboardEvent, Location, Modifiers},
     script_traits::{MouseButton, TouchEventType, TouchId},
 };
-use std::str::FromStr;
-
-use crate::renderer::qobject::QServoRenderer;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use crate::{
+    authenticationrequest::{
+        qobject::ServoAuthenticationRequest, QServoAuthenticationRequest, QServoCredentialStore,
+    },
+    beforeunloadrequest::{qobject::ServoBeforeUnloadRequest, QServoBeforeUnloadRequest},
+    browser::{QServoGeometryRequest, QServoLoadRequest},
+    browsingdata::QServoBrowsingDataTypes,
+    content::QServoContent,
+    cookies::{cookies_from_netscape, cookies_to_netscape, QServoCookie, QServoCookieRequest},
+    errorpage::{fill_template, QServoErrorPage},
+    fileselectionrequest::{
+        paths_from_qstringlist, qobject::ServoFileSelectionRequest, QServoFileSelectionRequest,
+    },
+    fullscreenrequest::{qobject::ServoFullScreenRequest, QServoFullScreenRequest},
+    javascript::QServoJavaScriptResult,
+    javascriptdialog::{qobject::ServoJavaScriptDialog, QServoJavaScriptDialogRequest},
+    mediasession::qobject::{PlaybackState, ServoMediaSession},
+    messagechannel::QServoMessageChannel,
+    permissionrequest::{
+        qobject::ServoPermissionRequest, QServoPermissionRequest, QServoPermissionStore,
+    },
+    profile::QServoProfile,
+    renderer::qobject::QServoRenderer,
+    settings::{qobject::ServoSettings, QServoSettings},
+    urlscheme::{resource_prefix_handler, QServoUrlSchemeHandlers, QServoUrlSchemeResponse},
+    webresourcerequest::{
+        qobject::ServoWebResourceRequest, QServoCannedResponses, QServoRequestInterceptor,
+        QServoWebResourceRequestPending, QServoWebResourceResponse,
+    },
+    windowheadless::QServoScreenGeometry,
+};
 
 impl qobject::QTouchEvent {
     fn point_count(&self) -> isize {
@@ -326,31 +762,168 @@ fn get_servo_code_from_scancode(code: i32) -> Code {
 }
 
 pub struct QServoWebViewRust {
+    allowed_message_origins: QStringList,
+    audio_muted: bool,
     can_go_back: bool,
     can_go_forward: bool,
+    error_page_template: QString,
     favicon_url: QUrl,
+    hovered_url: QUrl,
+    is_full_screen: bool,
+    load_progress: i32,
     loading: bool,
+    media_session: *mut ServoMediaSession,
+    recently_audible: bool,
+    profile: *mut ServoProfile,
+    /// The values of the assigned profile
+    profile_values: QServoProfile,
+    /// Increases with every assigned profile, so that changes to older ones are ignored
+    profile_generation: u64,
+    /// The connections to the assigned profile, dropping them disconnects
+    profile_connections: Vec<cxx_qt::QMetaObjectConnectionGuard>,
+    settings: *mut ServoSettings,
+    status_text: QString,
     title: QString,
     url: QUrl,
+    user_agent: QString,
+    volume: f64,
+    media_session_owned: cxx::UniquePtr<ServoMediaSession>,
+    settings_owned: cxx::UniquePtr<ServoSettings>,
     pub(crate) events: Vec<EmbedderEvent>,
     press_position: Option<QPointF>,
     navigation_allowed: bool,
     pub(crate) navigation_direction: Option<i32>,
+    /// A pending reload, the value is whether the cache should be bypassed
+    pub(crate) reload_requested: Option<bool>,
+    pub(crate) stop_requested: bool,
+    /// Whether the page is in fullscreen, or has been asked to be
+    page_full_screen: bool,
+    /// A change of fullscreen which has not been sent to the page yet
+    pub(crate) page_full_screen_change: Option<bool>,
+    full_screen_requests: Vec<cxx::UniquePtr<ServoFullScreenRequest>>,
+    /// There is no web view, for example after window.close()
+    pub(crate) blank: bool,
+    /// Servo or the content crashed, so no new frames are rendered
+    pub(crate) terminated: bool,
+    /// The Servo thread should be torn down and rebuilt
+    pub(crate) restart_requested: bool,
+    /// A changed error page which has not been sent to Servo yet
+    pub(crate) error_page: Option<Option<QServoErrorPage>>,
+    javascript_dialogs: Vec<cxx::UniquePtr<ServoJavaScriptDialog>>,
+    authentication_requests: Vec<cxx::UniquePtr<ServoAuthenticationRequest>>,
+    credentials: QServoCredentialStore,
+    /// A changed credential store which has not been sent to Servo yet
+    pub(crate) credential_store: Option<Option<QServoCredentialStore>>,
+    permission_requests: Vec<cxx::UniquePtr<ServoPermissionRequest>>,
+    /// Remembered permission decisions, persisted in the storage path of the profile
+    pub(crate) permissions: QServoPermissionStore,
+    /// A changed permission store which has not been sent to Servo yet
+    pub(crate) permission_store: Option<Option<QServoPermissionStore>>,
+    file_selection_requests: Vec<cxx::UniquePtr<ServoFileSelectionRequest>>,
+    web_resource_requests: Vec<cxx::UniquePtr<ServoWebResourceRequest>>,
+    canned_responses_registry: QServoCannedResponses,
+    /// Canned responses which have not been sent to Servo yet
+    pub(crate) canned_responses: Option<QServoCannedResponses>,
+    /// A changed file chooser result which has not been sent to Servo yet
+    pub(crate) file_chooser_result: Option<Option<Vec<String>>>,
+    before_unload_requests: Vec<cxx::UniquePtr<ServoBeforeUnloadRequest>>,
+    /// A close which is waiting for the page to allow it
+    pub(crate) close_requested: bool,
+    /// Scripts which have not been sent to Servo yet, with the id of their callback
+    pub(crate) javascript_requests: Vec<(u64, String)>,
+    javascript_callbacks: HashMap<u64, cxx::UniquePtr<qobject::QJSValue>>,
+    next_javascript_id: u64,
+    /// A changed message channel which has not been sent to Servo yet
+    pub(crate) message_channel: Option<QServoMessageChannel>,
+    /// Messages from postMessage as JSON, which have not been sent to Servo yet
+    pub(crate) posted_messages: Vec<String>,
+    url_scheme_handlers_registry: QServoUrlSchemeHandlers,
+    /// Changed scheme handlers which have not been sent to Servo yet
+    pub(crate) url_scheme_handlers: Option<QServoUrlSchemeHandlers>,
+    /// Content from loadHtml and loadData which has not been sent to Servo yet
+    pub(crate) content_requests: Vec<QServoContent>,
+    /// Cookie requests which have not been sent to Servo yet
+    pub(crate) cookie_requests: Vec<QServoCookieRequest>,
+    /// Browsing data to clear which has not been sent to Servo yet
+    pub(crate) browsing_data_requests: Vec<QServoBrowsingDataTypes>,
+    /// Browsing data to remove once Servo has been shut down for a restart
+    pub(crate) browsing_data_wipe: Option<QServoBrowsingDataTypes>,
+    /// A changed request interceptor which has not been sent to Servo yet
+    pub(crate) request_interceptor: Option<Option<Arc<dyn QServoRequestInterceptor>>>,
+    /// Changed Servo preferences which have not been sent to Servo yet
+    pub(crate) servo_settings: Option<QServoSettings>,
 }
 
 impl Default for QServoWebViewRust {
     fn default() -> Self {
+        let credentials = QServoCredentialStore::default();
+        let permissions = QServoPermissionStore::default();
         Self {
+            allowed_message_origins: QStringList::default(),
+            audio_muted: false,
             can_go_back: false,
             can_go_forward: false,
+            error_page_template: QString::default(),
             favicon_url: QUrl::default(),
+            hovered_url: QUrl::default(),
+            is_full_screen: false,
+            load_progress: 0,
             loading: false,
+            media_session: std::ptr::null_mut(),
+            recently_audible: false,
+            profile: std::ptr::null_mut(),
+            profile_values: QServoProfile::default(),
+            profile_generation: 0,
+            profile_connections: vec![],
+            settings: std::ptr::null_mut(),
+            status_text: QString::default(),
             title: QString::default(),
             url: QUrl::default(),
+            user_agent: QString::default(),
+            volume: 1.0,
+            media_session_owned: cxx::UniquePtr::null(),
+            settings_owned: cxx::UniquePtr::null(),
             events: vec![],
             press_position: None,
             navigation_allowed: true,
             navigation_direction: None,
+            reload_requested: None,
+            stop_requested: false,
+            page_full_screen: false,
+            page_full_screen_change: None,
+            full_screen_requests: vec![],
+            blank: false,
+            terminated: false,
+            restart_requested: false,
+            error_page: None,
+            javascript_dialogs: vec![],
+            authentication_requests: vec![],
+            // The store is shared, so changes reach Servo after it has been sent
+            credential_store: Some(Some(credentials.clone())),
+            credentials,
+            permission_requests: vec![],
+            permission_store: Some(Some(permissions.clone())),
+            permissions,
+            file_selection_requests: vec![],
+            web_resource_requests: vec![],
+            canned_responses_registry: QServoCannedResponses::default(),
+            canned_responses: None,
+            file_chooser_result: None,
+            before_unload_requests: vec![],
+            close_requested: false,
+            javascript_requests: vec![],
+            javascript_callbacks: HashMap::new(),
+            next_javascript_id: 0,
+            message_channel: None,
+            posted_messages: vec![],
+            url_scheme_handlers_registry: QServoUrlSchemeHandlers::default(),
+            url_scheme_handlers: None,
+            content_requests: vec![],
+            cookie_requests: vec![],
+            browsing_data_requests: vec![],
+            browsing_data_wipe: None,
+            request_interceptor: None,
+            servo_settings: None,
         }
     }
 }
@@ -360,6 +933,10 @@ impl qobject::ServoWebView {
         QServoRenderer::new().into_raw() as *mut qobject::QQuickFramebufferObjectRenderer
     }
 
+    fn exit_full_screen(self: Pin<&mut Self>) {
+        self.set_is_full_screen(false);
+    }
+
     fn go_back(mut self: Pin<&mut Self>) {
         self.as_mut().rust_mut().navigation_direction = Some(-1);
         self.as_mut().update();
@@ -370,6 +947,854 @@ impl qobject::ServoWebView {
         self.as_mut().update();
     }
 
+    /// Set a callback which builds the HTML shown for failed loads
+    ///
+    /// This replaces the errorPageTemplate, until the errorPageTemplate is changed again.
+    pub fn set_error_page_handler(
+        mut self: Pin<&mut Self>,
+        handler: impl Fn(&url::Url, qobject::ErrorDomain, &str) -> String + Send + Sync + 'static,
+    ) {
+        self.as_mut().rust_mut().error_page = Some(Some(QServoErrorPage::new(Arc::new(handler))));
+        self.as_mut().update();
+    }
+
+    fn apply_error_page_template(mut self: Pin<&mut Self>) {
+        let template = String::from(self.as_ref().error_page_template());
+        if template.is_empty() {
+            self.as_mut().rust_mut().error_page = Some(None);
+            self.as_mut().update();
+        } else {
+            self.as_mut().set_error_page_handler(move |url, _, reason| {
+                fill_template(&template, url, reason)
+            });
+        }
+    }
+
+    pub(crate) fn request_authentication(
+        mut self: Pin<&mut Self>,
+        request: QServoAuthenticationRequest,
+    ) {
+        // Without a handler nobody can answer, so don't leave the load hanging
+        if !qobject::qobject_is_signal_connected(
+            self.as_ref().get_ref(),
+            "authenticationRequired(ServoAuthenticationRequest*)",
+        ) {
+            request.cancel();
+            return;
+        }
+
+        // Destroy any requests which have been answered
+        self.as_mut()
+            .rust_mut()
+            .authentication_requests
+            .retain(|request| !request.is_answered());
+
+        let request = request.into_qobject();
+        unsafe {
+            self.as_mut().authentication_required(request.as_mut_ptr());
+        }
+        self.as_mut()
+            .rust_mut()
+            .authentication_requests
+            .push(request);
+    }
+
+    fn clear_credentials(self: Pin<&mut Self>) {
+        self.credentials.clear();
+    }
+
+    fn store_credentials(self: Pin<&mut Self>, url: &QUrl, user: &QString, password: &QString) {
+        let Ok(url) = url::Url::try_from(url) else {
+            return;
+        };
+        self.credentials.insert(&url, user.to_string(), password.to_string());
+    }
+
+    /// The current geometry of the screen and window that the item is shown in
+    pub(crate) fn screen_geometry(&self) -> QServoScreenGeometry {
+        let screen = qobject::qquick_item_screen_geometry(self);
+        let screen_avail = qobject::qquick_item_screen_available_geometry(self);
+        let window = qobject::qquick_item_window_geometry(self);
+        QServoScreenGeometry {
+            screen: Size2D::new(screen.width(), screen.height()),
+            screen_avail: Size2D::new(screen_avail.width(), screen_avail.height()),
+            window: Rect::new(
+                Point2D::new(window.x(), window.y()),
+                Size2D::new(window.width(), window.height()),
+            ),
+        }
+    }
+
+    pub(crate) fn media_session_mut(self: Pin<&mut Self>) -> Option<Pin<&mut ServoMediaSession>> {
+        let rust = unsafe { self.rust_mut().get_unchecked_mut() };
+        rust.media_session_owned.as_mut()
+    }
+
+    /// The profile which Servo is started with, by default nothing is persisted
+    pub(crate) fn servo_profile(&self) -> QServoProfile {
+        self.profile_values.clone()
+    }
+
+    /// Copy the values of an assigned profile, as QML owns it and can destroy it
+    fn profile_assigned(mut self: Pin<&mut Self>) {
+        let generation = self.as_ref().profile_generation + 1;
+        self.as_mut().rust_mut().profile_generation = generation;
+        // Stop listening to the previous profile
+        self.as_mut().rust_mut().profile_connections.clear();
+
+        let profile = *self.as_ref().profile();
+        let values = match unsafe { profile.as_mut() } {
+            Some(profile) => {
+                // The profile has just been assigned so it is alive, later changes are
+                // sent to us by value
+                let mut profile = unsafe { Pin::new_unchecked(profile) };
+                let connections = profile.as_mut().watch(self.qt_thread(), generation);
+                self.as_mut().rust_mut().profile_connections = connections;
+                profile.as_ref().profile()
+            }
+            None => QServoProfile::default(),
+        };
+        self.as_mut().servo_profile_changed(generation, values);
+    }
+
+    /// The values of the assigned profile changed, Servo is restarted to use them
+    pub(crate) fn servo_profile_changed(
+        mut self: Pin<&mut Self>,
+        generation: u64,
+        profile: QServoProfile,
+    ) {
+        if generation != self.as_ref().profile_generation || profile == self.profile_values {
+            return;
+        }
+
+        self.as_mut().rust_mut().profile_values = profile;
+        self.as_mut().restart();
+    }
+
+    /// Send the changed settings to Servo
+    pub(crate) fn apply_servo_settings(mut self: Pin<&mut Self>, settings: QServoSettings) {
+        self.as_mut().rust_mut().servo_settings = Some(settings);
+        self.as_mut().update();
+    }
+
+    /// Whether the page is playing media which can be heard
+    ///
+    /// TODO: servo-media does not report audible output, so use the playback
+    /// state of the media session instead
+    pub(crate) fn update_recently_audible(mut self: Pin<&mut Self>) {
+        let playing = self
+            .as_mut()
+            .media_session_mut()
+            .map(|media_session| *media_session.as_ref().playback_state() == PlaybackState::Playing)
+            .unwrap_or(false);
+        let audible = playing && !*self.as_ref().audio_muted() && *self.as_ref().volume() > 0.0;
+        self.as_mut().set_recently_audible(audible);
+    }
+
+    /// recentlyAudible is read only in QML, so it has no generated setter
+    fn set_recently_audible(mut self: Pin<&mut Self>, audible: bool) {
+        if self.recently_audible != audible {
+            self.as_mut().rust_mut().recently_audible = audible;
+            self.recently_audible_changed();
+        }
+    }
+
+    /// Reset the item after the web view has been closed
+    pub(crate) fn webview_closed(mut self: Pin<&mut Self>) {
+        self.as_mut().rust_mut().blank = true;
+        if let Some(media_session) = self.as_mut().media_session_mut() {
+            media_session.reset();
+        }
+        self.as_mut().set_recently_audible(false);
+        self.as_mut().set_can_go_back(false);
+        self.as_mut().set_can_go_forward(false);
+        self.as_mut().set_favicon_url(QUrl::default());
+        self.as_mut().set_hovered_url(QUrl::default());
+        self.as_mut().rust_mut().page_full_screen = false;
+        self.as_mut().rust_mut().page_full_screen_change = None;
+        self.as_mut().set_is_full_screen(false);
+        self.as_mut().set_load_progress(0);
+        self.as_mut().set_status_text(QString::default());
+        self.as_mut().set_title(QString::default());
+        self.as_mut().set_url(QUrl::default());
+        self.as_mut().window_close_requested();
+        self.as_mut().update();
+    }
+
+    /// Servo or the content of the page crashed
+    ///
+    /// Unless the last frame is kept, the error page which replaces the page is shown.
+    pub(crate) fn render_process_terminated_with(
+        mut self: Pin<&mut Self>,
+        reason: &str,
+        backtrace: &str,
+        keep_frame: bool,
+    ) {
+        self.as_mut().rust_mut().terminated = keep_frame;
+        self.as_mut().set_loading(false);
+        self.as_mut()
+            .render_process_terminated(QString::from(reason), QString::from(backtrace));
+    }
+
+    pub(crate) fn request_geometry_change(
+        mut self: Pin<&mut Self>,
+        request: QServoGeometryRequest,
+    ) {
+        let window = qobject::qquick_item_window_geometry(self.as_ref().get_ref());
+        let geometry = match request {
+            QServoGeometryRequest::Move(x, y) => QRect::new(x, y, window.width(), window.height()),
+            QServoGeometryRequest::Resize(width, height) => {
+                QRect::new(window.x(), window.y(), width, height)
+            }
+        };
+        self.as_mut().geometry_change_requested(geometry);
+    }
+
+    pub(crate) fn request_before_unload(
+        mut self: Pin<&mut Self>,
+        request: QServoBeforeUnloadRequest,
+    ) {
+        // Without a handler nobody can answer, so let the page unload
+        if !qobject::qobject_is_signal_connected(
+            self.as_ref().get_ref(),
+            "beforeUnloadRequested(ServoBeforeUnloadRequest*)",
+        ) {
+            request.accept();
+            return;
+        }
+
+        // Destroy any requests which have been answered
+        self.as_mut()
+            .rust_mut()
+            .before_unload_requests
+            .retain(|request| !request.is_answered());
+
+        let request = request.into_qobject();
+        unsafe {
+            self.as_mut().before_unload_requested(request.as_mut_ptr());
+        }
+        self.as_mut()
+            .rust_mut()
+            .before_unload_requests
+            .push(request);
+    }
+
+    pub(crate) fn request_file_selection(
+        mut self: Pin<&mut Self>,
+        request: QServoFileSelectionRequest,
+    ) {
+        // Without a handler nobody can answer, so select no files
+        if !qobject::qobject_is_signal_connected(
+            self.as_ref().get_ref(),
+            "fileSelectionRequested(ServoFileSelectionRequest*)",
+        ) {
+            request.respond(None);
+            return;
+        }
+
+        // Destroy any requests which have been answered
+        self.as_mut()
+            .rust_mut()
+            .file_selection_requests
+            .retain(|request| !request.is_answered());
+
+        let request = request.into_qobject();
+        unsafe {
+            self.as_mut().file_selection_requested(request.as_mut_ptr());
+        }
+        self.as_mut()
+            .rust_mut()
+            .file_selection_requests
+            .push(request);
+    }
+
+    fn set_file_chooser_result(mut self: Pin<&mut Self>, files: &QStringList) {
+        let files = paths_from_qstringlist(files);
+        self.as_mut().rust_mut().file_chooser_result =
+            Some(Some(files).filter(|files| !files.is_empty()));
+        self.as_mut().update();
+    }
+
+    /// Whether QML has a handler for web resource requests
+    pub(crate) fn intercepts_requests_in_qml(&self) -> bool {
+        qobject::qobject_is_signal_connected(self, "webResourceRequested(ServoWebResourceRequest*)")
+    }
+
+    pub(crate) fn request_web_resource(
+        mut self: Pin<&mut Self>,
+        request: QServoWebResourceRequestPending,
+    ) {
+        // The handler may have gone since Servo was told about it
+        if !self.as_ref().intercepts_requests_in_qml() {
+            request.allow();
+            return;
+        }
+
+        // Destroy any requests which have been answered
+        self.as_mut()
+            .rust_mut()
+            .web_resource_requests
+            .retain(|request| !request.is_answered());
+
+        let mut request = request.into_qobject();
+        unsafe {
+            self.as_mut().web_resource_requested(request.as_mut_ptr());
+        }
+        self.as_mut()
+            .rust_mut()
+            .web_resource_requests
+            .push(request);
+    }
+
+    fn set_canned_response(
+        mut self: Pin<&mut Self>,
+        url: &QUrl,
+        status_code: i32,
+        content_type: &QString,
+        body: &QByteArray,
+    ) -> bool {
+        let Ok(url) = url::Url::try_from(url) else {
+            println!("invalid url for canned response");
+            return false;
+        };
+        let status_code = match u16::try_from(status_code) {
+            Ok(status_code @ 100..=599) => status_code,
+            _ => {
+                println!("invalid status code for canned response: {status_code}");
+                return false;
+            }
+        };
+
+        let response = QServoWebResourceResponse {
+            status_code,
+            headers: vec![("Content-Type".to_owned(), content_type.to_string())],
+            body: body.as_slice().to_vec(),
+        };
+        let registry = self.as_ref().canned_responses_registry.clone();
+        registry.insert(url, response);
+        self.as_mut().rust_mut().canned_responses = Some(registry);
+        self.as_mut().update();
+        true
+    }
+
+    fn clear_canned_responses(self: Pin<&mut Self>) {
+        // Servo shares the responses, so there is nothing to send
+        self.as_ref().canned_responses_registry.clear();
+    }
+
+    /// Set an interceptor which decides what happens to each web resource request
+    ///
+    /// Requests which the interceptor allows are given to QML if there is a handler.
+    // This is the Rust API of interception, nothing in this crate sets an interceptor
+    #[allow(dead_code)]
+    pub fn set_request_interceptor(
+        mut self: Pin<&mut Self>,
+        interceptor: Option<Arc<dyn QServoRequestInterceptor>>,
+    ) {
+        self.as_mut().rust_mut().request_interceptor = Some(interceptor);
+        self.as_mut().update();
+    }
+
+    /// The page entered or left fullscreen by itself
+    pub(crate) fn request_full_screen(mut self: Pin<&mut Self>, toggle_on: bool) {
+        // A change which was asked for by QML has nothing to answer
+        let page_full_screen = &mut self.as_mut().rust_mut().page_full_screen;
+        if std::mem::replace(page_full_screen, toggle_on) == toggle_on {
+            return;
+        }
+
+        let origin = url::Url::try_from(self.as_ref().url())
+            .ok()
+            .and_then(|url| url::Url::parse(&url.origin().ascii_serialization()).ok());
+        let qt_thread = self.qt_thread();
+        let request = QServoFullScreenRequest::new(
+            origin,
+            toggle_on,
+            Box::new(move |full_screen| {
+                let _ = qt_thread.queue(move |mut webview| {
+                    webview.as_mut().set_is_full_screen(full_screen);
+                    // A rejected request leaves isFullScreen as it was
+                    webview.full_screen_changed();
+                });
+            }),
+        );
+
+        // Without a handler the page has its way
+        if !qobject::qobject_is_signal_connected(
+            self.as_ref().get_ref(),
+            "fullScreenRequested(ServoFullScreenRequest*)",
+        ) {
+            request.accept();
+            return;
+        }
+
+        // Destroy any requests which have been answered
+        self.as_mut()
+            .rust_mut()
+            .full_screen_requests
+            .retain(|request| !request.is_answered());
+
+        let request = request.into_qobject();
+        unsafe {
+            self.as_mut().full_screen_requested(request.as_mut_ptr());
+        }
+        self.as_mut().rust_mut().full_screen_requests.push(request);
+    }
+
+    /// Bring the page into the fullscreen state of isFullScreen
+    fn full_screen_changed(mut self: Pin<&mut Self>) {
+        let full_screen = *self.as_ref().is_full_screen();
+        if self.as_ref().page_full_screen != full_screen {
+            self.as_mut().rust_mut().page_full_screen = full_screen;
+            self.as_mut().rust_mut().page_full_screen_change = Some(full_screen);
+            self.as_mut().update();
+        }
+    }
+
+    pub(crate) fn request_permission(mut self: Pin<&mut Self>, request: QServoPermissionRequest) {
+        // Without a handler nobody can answer, so deny by default
+        if !qobject::qobject_is_signal_connected(
+            self.as_ref().get_ref(),
+            "permissionRequested(ServoPermissionRequest*)",
+        ) {
+            request.deny();
+            return;
+        }
+
+        // Destroy any requests which have been answered
+        self.as_mut()
+            .rust_mut()
+            .permission_requests
+            .retain(|request| !request.is_answered());
+
+        let request = request.into_qobject();
+        unsafe {
+            self.as_mut().permission_requested(request.as_mut_ptr());
+        }
+        self.as_mut().rust_mut().permission_requests.push(request);
+    }
+
+    fn clear_permissions(self: Pin<&mut Self>, url: &QUrl) {
+        if let Ok(url) = url::Url::try_from(url) {
+            self.permissions.clear_origin(&url.origin());
+        }
+    }
+
+    fn set_permission(self: Pin<&mut Self>, url: &QUrl, feature: &QString, granted: bool) {
+        if let Ok(url) = url::Url::try_from(url) {
+            self.permissions.set_policy(&url.origin(), &feature.to_string(), granted);
+        }
+    }
+
+    pub(crate) fn request_javascript_dialog(
+        mut self: Pin<&mut Self>,
+        request: QServoJavaScriptDialogRequest,
+    ) {
+        // Without a handler nobody can answer, so don't leave the page hanging
+        if !qobject::qobject_is_signal_connected(
+            self.as_ref().get_ref(),
+            "javaScriptDialogRequested(ServoJavaScriptDialog*)",
+        ) {
+            request.dismiss();
+            return;
+        }
+
+        // Destroy any dialogs which have been answered
+        self.as_mut()
+            .rust_mut()
+            .javascript_dialogs
+            .retain(|dialog| !dialog.is_answered());
+
+        let dialog = request.into_qobject();
+        unsafe {
+            self.as_mut()
+                .javascript_dialog_requested(dialog.as_mut_ptr());
+        }
+        self.as_mut().rust_mut().javascript_dialogs.push(dialog);
+    }
+
+    fn load_data(
+        mut self: Pin<&mut Self>,
+        data: &QByteArray,
+        mime_type: &QString,
+        base_url: &QUrl,
+    ) {
+        let content = QServoContent::data(
+            data.as_slice(),
+            &mime_type.to_string(),
+            url::Url::try_from(base_url).ok(),
+        );
+        self.as_mut().load_content(content);
+    }
+
+    fn load_html(mut self: Pin<&mut Self>, html: &QString, base_url: &QUrl) {
+        let content = QServoContent::html(&html.to_string(), url::Url::try_from(base_url).ok());
+        self.as_mut().load_content(content);
+    }
+
+    fn load_content(mut self: Pin<&mut Self>, content: Result<QServoContent, String>) {
+        match content {
+            Ok(content) => {
+                self.as_mut().rust_mut().blank = false;
+                self.as_mut().rust_mut().content_requests.push(content);
+                self.as_mut().update();
+            }
+            Err(err) => println!("failed to load content: {err}"),
+        }
+    }
+
+    fn post_message(mut self: Pin<&mut Self>, data: &QVariant) {
+        let json = qobject::qvariant_to_json(data);
+        self.as_mut().rust_mut().posted_messages.push(json);
+        self.as_mut().update();
+    }
+
+    pub(crate) fn load_request_changed_with(self: Pin<&mut Self>, request: &QServoLoadRequest) {
+        self.load_request_changed(qobject::qvariant_from_json(&request.to_json()));
+    }
+
+    /// Emit a message from the page, the data is given as JSON
+    pub(crate) fn receive_message(mut self: Pin<&mut Self>, origin: &str, json: &str) {
+        let data = qobject::qvariant_from_json(json);
+        self.as_mut().message_received(QString::from(origin), data);
+    }
+
+    fn sync_allowed_message_origins(mut self: Pin<&mut Self>) {
+        let allowed_origins: &QList<QString> = self.as_ref().allowed_message_origins().as_ref();
+        let allowed_origins = allowed_origins.iter().map(String::from).collect();
+        self.as_mut().rust_mut().message_channel = Some(QServoMessageChannel::new(allowed_origins));
+        self.as_mut().update();
+    }
+
+    /// Serve URLs of the scheme with a callback, which is called on the Servo thread
+    ///
+    /// This needs to be called before a URL of the scheme is loaded.
+    pub fn register_url_scheme_handler(
+        mut self: Pin<&mut Self>,
+        scheme: &str,
+        handler: impl Fn(&url::Url) -> Option<QServoUrlSchemeResponse> + Send + Sync + 'static,
+    ) {
+        let registry = self.as_ref().url_scheme_handlers_registry.clone();
+        registry.register(scheme, Arc::new(handler));
+        self.as_mut().rust_mut().url_scheme_handlers = Some(registry);
+        self.as_mut().update();
+    }
+
+    fn register_url_scheme_resource_prefix(
+        self: Pin<&mut Self>,
+        scheme: &QString,
+        prefix: &QString,
+    ) {
+        let handler = resource_prefix_handler(&prefix.to_string());
+        self.register_url_scheme_handler(&scheme.to_string(), move |url| handler(url));
+    }
+
+    pub(crate) fn reload(mut self: Pin<&mut Self>) {
+        // Do not downgrade a pending reload that bypasses the cache
+        let bypass_cache = self.as_ref().reload_requested.unwrap_or(false);
+        self.as_mut().rust_mut().reload_requested = Some(bypass_cache);
+        self.as_mut().update();
+    }
+
+    fn reload_bypassing_cache(mut self: Pin<&mut Self>) {
+        self.as_mut().rust_mut().reload_requested = Some(true);
+        self.as_mut().update();
+    }
+
+    fn restart(mut self: Pin<&mut Self>) {
+        // Results of scripts will never arrive from the old Servo
+        let ids: Vec<u64> = self.as_ref().javascript_callbacks.keys().copied().collect();
+        for id in ids {
+            self.as_mut()
+                .javascript_result(id, Err("Servo was restarted".to_owned()));
+        }
+
+        self.as_mut().rust_mut().terminated = false;
+        self.as_mut().rust_mut().restart_requested = true;
+        self.as_mut().update();
+    }
+
+    fn run_javascript(mut self: Pin<&mut Self>, script: &QString, callback: &qobject::QJSValue) {
+        let id = self.as_mut().add_javascript_callback(callback);
+        self.as_mut()
+            .rust_mut()
+            .javascript_requests
+            .push((id, script.to_string()));
+        self.as_mut().update();
+    }
+
+    /// Evaluate a script in the page without waiting for its result
+    pub(crate) fn run_script(mut self: Pin<&mut Self>, script: String) {
+        let id = self.as_ref().next_javascript_id;
+        let mut rust = self.as_mut().rust_mut();
+        rust.next_javascript_id += 1;
+        rust.javascript_requests.push((id, script));
+        self.as_mut().update();
+    }
+
+    /// Keep a QML callback until javascript_result is called with its id
+    fn add_javascript_callback(mut self: Pin<&mut Self>, callback: &qobject::QJSValue) -> u64 {
+        let id = self.as_ref().next_javascript_id;
+        let mut rust = self.as_mut().rust_mut();
+        rust.next_javascript_id += 1;
+        rust.javascript_callbacks
+            .insert(id, qobject::qjsvalue_unique_ptr(callback));
+        id
+    }
+
+    /// Give the result of a script to its callback, an error is the second argument
+    pub(crate) fn javascript_result(
+        mut self: Pin<&mut Self>,
+        id: u64,
+        result: QServoJavaScriptResult,
+    ) {
+        if let Some(mut callback) = self.as_mut().rust_mut().javascript_callbacks.remove(&id) {
+            if let Some(callback) = callback.as_mut() {
+                match result {
+                    Ok(json) => {
+                        qobject::qjsvalue_call_with_json(self.as_ref().get_ref(), callback, &json)
+                    }
+                    Err(err) => qobject::qjsvalue_call_with_error(callback, &QString::from(&err)),
+                }
+            }
+        }
+    }
+
+    fn push_cookie_request(mut self: Pin<&mut Self>, request: QServoCookieRequest) {
+        self.as_mut().rust_mut().cookie_requests.push(request);
+        self.as_mut().update();
+    }
+
+    /// Give the callback the cookies of the host which are sent to the current page,
+    /// an empty host gives all of those
+    ///
+    /// The callback is called on another thread.
+    pub fn list_cookies(
+        self: Pin<&mut Self>,
+        host: &str,
+        callback: impl FnOnce(Vec<QServoCookie>) + Send + 'static,
+    ) {
+        let host = host.to_owned();
+        self.push_cookie_request(QServoCookieRequest::List(Box::new(move |cookies| {
+            callback(
+                cookies
+                    .into_iter()
+                    .filter(|cookie| cookie.matches_host(&host))
+                    .collect(),
+            );
+        })));
+    }
+
+    /// Add a cookie, cookieAdded is emitted once Servo has it
+    ///
+    /// The domain has to be empty or the host of the current page.
+    pub fn add_cookie(self: Pin<&mut Self>, cookie: QServoCookie) {
+        let qt_thread = self.qt_thread();
+        let added = cookie.clone();
+        self.push_cookie_request(QServoCookieRequest::Set(
+            cookie,
+            Box::new(move |result| match result {
+                Ok(()) => {
+                    if qt_thread
+                        .queue(move |webview| webview.cookie_changed(&added, true))
+                        .is_err()
+                    {
+                        println!("failed to queue cookie, the web view has gone");
+                    }
+                }
+                Err(err) => println!("{err}"),
+            }),
+        ));
+    }
+
+    /// Remove the cookies of the host with the name, or all of them without a name,
+    /// which are sent to the current page
+    pub fn remove_cookies(self: Pin<&mut Self>, name: Option<&str>, host: &str) {
+        let qt_thread = self.qt_thread();
+        let name = name.map(str::to_owned);
+        self.list_cookies(host, move |cookies| {
+            let cookies: Vec<QServoCookie> = cookies
+                .into_iter()
+                .filter(|cookie| name.as_ref().map_or(true, |name| *name == cookie.name))
+                .collect();
+            if cookies.is_empty() {
+                return;
+            }
+
+            if qt_thread
+                .queue(move |mut webview| {
+                    for cookie in cookies {
+                        webview.as_mut().expire_cookie(cookie);
+                    }
+                })
+                .is_err()
+            {
+                println!("failed to queue cookies, the web view has gone");
+            }
+        });
+    }
+
+    /// Servo removes a cookie when it is set with an expiry in the past
+    fn expire_cookie(self: Pin<&mut Self>, cookie: QServoCookie) {
+        let qt_thread = self.qt_thread();
+        self.push_cookie_request(QServoCookieRequest::Set(
+            cookie.expired(),
+            Box::new(move |result| match result {
+                Ok(()) => {
+                    if qt_thread
+                        .queue(move |webview| webview.cookie_changed(&cookie, false))
+                        .is_err()
+                    {
+                        println!("failed to queue cookie, the web view has gone");
+                    }
+                }
+                Err(err) => println!("{err}"),
+            }),
+        ));
+    }
+
+    fn cookie_changed(self: Pin<&mut Self>, cookie: &QServoCookie, added: bool) {
+        let cookie = qobject::qvariant_from_json(&cookie.to_json().to_string());
+        if added {
+            self.cookie_added(cookie);
+        } else {
+            self.cookie_removed(cookie);
+        }
+    }
+
+    /// Add the cookies of a Netscape cookie file, returns how many were read
+    pub fn import_netscape_cookies(mut self: Pin<&mut Self>, netscape: &str) -> usize {
+        let cookies = cookies_from_netscape(netscape);
+        let count = cookies.len();
+        for cookie in cookies {
+            self.as_mut().add_cookie(cookie);
+        }
+        count
+    }
+
+    /// Give the callback the cookies of the host in the Netscape cookie file format
+    ///
+    /// The callback is called on another thread.
+    pub fn export_netscape_cookies(
+        self: Pin<&mut Self>,
+        host: &str,
+        callback: impl FnOnce(String) + Send + 'static,
+    ) {
+        self.list_cookies(host, move |cookies| callback(cookies_to_netscape(&cookies)));
+    }
+
+    /// Clear browsing data of every site, browsingDataCleared is emitted with the types
+    /// which were cleared
+    ///
+    /// Clearing cookies or storage restarts Servo, see clearBrowsingData.
+    pub fn clear_browsing_data(mut self: Pin<&mut Self>, types: QServoBrowsingDataTypes) {
+        if types.needs_restart() {
+            self.as_mut()
+                .rust_mut()
+                .browsing_data_wipe
+                .get_or_insert_with(QServoBrowsingDataTypes::default)
+                .merge(types);
+            self.restart();
+        } else {
+            self.as_mut().rust_mut().browsing_data_requests.push(types);
+            self.as_mut().update();
+        }
+    }
+
+    fn clear_browsing_data_named(self: Pin<&mut Self>, types: &QStringList) -> bool {
+        let types: &QList<QString> = types.as_ref();
+        let names: Vec<String> = types.iter().map(String::from).collect();
+        match QServoBrowsingDataTypes::from_names(names.iter().map(String::as_str)) {
+            Ok(types) => {
+                self.clear_browsing_data(types);
+                true
+            }
+            Err(err) => {
+                println!("{err}");
+                false
+            }
+        }
+    }
+
+    pub(crate) fn browsing_data_cleared_with(self: Pin<&mut Self>, types: QServoBrowsingDataTypes) {
+        let mut names = QList::<QString>::default();
+        for name in types.names() {
+            names.append(QString::from(name));
+        }
+        self.browsing_data_cleared(QStringList::from(&names));
+    }
+
+    fn clear_cookies(self: Pin<&mut Self>, host: &QString) {
+        self.remove_cookies(None, &host.to_string());
+    }
+
+    fn cookies(mut self: Pin<&mut Self>, host: &QString, callback: &qobject::QJSValue) {
+        let id = self.as_mut().add_javascript_callback(callback);
+        let qt_thread = self.qt_thread();
+        self.list_cookies(&host.to_string(), move |cookies| {
+            let json =
+                serde_json::Value::Array(cookies.iter().map(QServoCookie::to_json).collect())
+                    .to_string();
+            if qt_thread
+                .queue(move |webview| webview.javascript_result(id, Ok(json)))
+                .is_err()
+            {
+                println!("failed to queue cookies, the web view has gone");
+            }
+        });
+    }
+
+    fn delete_cookie(self: Pin<&mut Self>, name: &QString, host: &QString) {
+        self.remove_cookies(Some(&name.to_string()), &host.to_string());
+    }
+
+    fn export_cookies(mut self: Pin<&mut Self>, host: &QString, callback: &qobject::QJSValue) {
+        let id = self.as_mut().add_javascript_callback(callback);
+        let qt_thread = self.qt_thread();
+        self.export_netscape_cookies(&host.to_string(), move |netscape| {
+            let json = serde_json::Value::String(netscape).to_string();
+            if qt_thread
+                .queue(move |webview| webview.javascript_result(id, Ok(json)))
+                .is_err()
+            {
+                println!("failed to queue cookies, the web view has gone");
+            }
+        });
+    }
+
+    fn import_cookies(self: Pin<&mut Self>, netscape: &QString) -> i32 {
+        self.import_netscape_cookies(&netscape.to_string()) as i32
+    }
+
+    fn set_cookie(self: Pin<&mut Self>, cookie: &QVariant) -> bool {
+        let cookie = serde_json::from_str(&qobject::qvariant_to_json(cookie))
+            .ok()
+            .and_then(|value| QServoCookie::from_json(&value));
+        match cookie {
+            Some(cookie) => {
+                self.add_cookie(cookie);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn stop(mut self: Pin<&mut Self>) {
+        self.as_mut().rust_mut().stop_requested = true;
+        self.as_mut().update();
+    }
+
+    fn try_close(mut self: Pin<&mut Self>) {
+        // After a crash there is no page left to ask
+        if self.as_ref().terminated {
+            self.as_mut().window_close_requested();
+            return;
+        }
+
+        self.as_mut().rust_mut().close_requested = true;
+        self.as_mut().update();
+    }
+
     fn key_event(mut self: Pin<&mut Self>, event: *mut qobject::QKeyEvent, state: KeyState) {
         if let Some(event) = unsafe { event.as_ref() } {
             let keyboard_event = KeyboardEvent {
@@ -574,9 +1999,66 @@ impl cxx_qt::Initialize for qobject::ServoWebView {
         self.as_mut().set_accept_touch_events(true);
         self.as_mut().set_mirror_vertically(true);
 
+        // The media session is owned by us and exposed as a constant pointer property,
+        // so it is set before QML can read it
+        let mut media_session = ServoMediaSession::new(self.qt_thread());
+        let media_session_ptr = unsafe { media_session.pin_mut().get_unchecked_mut() as *mut _ };
+        self.as_mut().rust_mut().media_session_owned = media_session;
+        self.as_mut().rust_mut().media_session = media_session_ptr;
+
+        // The settings are owned by us too, they are read when Servo starts
+        let mut settings = ServoSettings::new(self.qt_thread());
+        let settings_ptr = unsafe { settings.pin_mut().get_unchecked_mut() as *mut _ };
+        self.as_mut().rust_mut().servo_settings = Some(settings.settings().clone());
+        self.as_mut().rust_mut().settings_owned = settings;
+        self.as_mut().rust_mut().settings = settings_ptr;
+
+        self.as_mut()
+            .on_profile_changed(|qobject| {
+                qobject.profile_assigned();
+            })
+            .release();
+
         // When the URL changes trigger QQuickFramebufferObject::update
         // which then triggers QQuickFramebufferObject::Renderer::synchronize
-        self.on_url_changed(|qobject| {
+        self.as_mut()
+            .on_url_changed(|mut qobject| {
+                // Loading a new URL brings back content after the web view was closed
+                if qobject.as_ref().url().is_valid() {
+                    qobject.as_mut().rust_mut().blank = false;
+                }
+                qobject.update();
+            })
+            .release();
+
+        self.as_mut()
+            .on_is_full_screen_changed(|qobject| {
+                qobject.full_screen_changed();
+            })
+            .release();
+
+        self.as_mut()
+            .on_error_page_template_changed(|qobject| {
+                qobject.apply_error_page_template();
+            })
+            .release();
+
+        self.as_mut()
+            .on_allowed_message_origins_changed(|qobject| {
+                qobject.sync_allowed_message_origins();
+            })
+            .release();
+
+        // Audio settings are sent to Servo from the renderer
+        self.as_mut()
+            .on_audio_muted_changed(|mut qobject| {
+                qobject.as_mut().update_recently_audible();
+                qobject.update();
+            })
+            .release();
+
+        self.on_volume_changed(|mut qobject| {
+            qobject.as_mut().update_recently_audible();
             qobject.update();
         })
         .release();
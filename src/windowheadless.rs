@@ -10,12 +10,22 @@ use servo::{
     euclid::{Point2D, Rect, Scale, Size2D},
     rendering_context::RenderingContext,
     servo_geometry::DeviceIndependentPixel,
+    style_traits::DevicePixel,
 };
 use surfman::{Connection, SurfaceType};
 
+/// The geometry of the screen and window that the webview is shown in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct QServoScreenGeometry {
+    pub(crate) screen: Size2D<i32, DevicePixel>,
+    pub(crate) screen_avail: Size2D<i32, DevicePixel>,
+    pub(crate) window: Rect<i32, DevicePixel>,
+}
+
 pub(crate) struct QServoWindowHeadless {
     animation_state: Cell<AnimationState>,
     rendering_context: RenderingContext,
+    screen_geometry: Cell<Option<QServoScreenGeometry>>,
 }
 
 impl QServoWindowHeadless {
@@ -32,8 +42,13 @@ impl QServoWindowHeadless {
         Self {
             rendering_context,
             animation_state: Cell::new(AnimationState::Idle),
+            screen_geometry: Cell::new(None),
         }
     }
+
+    pub fn set_screen_geometry(&self, screen_geometry: QServoScreenGeometry) {
+        self.screen_geometry.set(Some(screen_geometry));
+    }
 }
 
 impl WindowMethods for QServoWindowHeadless {
@@ -45,11 +60,17 @@ impl WindowMethods for QServoWindowHeadless {
             .map(|info| Size2D::from_untyped(info.size))
             .unwrap_or(Size2D::new(0, 0));
         let origin = Point2D::origin();
-        EmbedderCoordinates {
-            hidpi_factor: Scale::new(1.0),
+        // Until Qt has told us about the screen assume that the surface fills it
+        let screen_geometry = self.screen_geometry.get().unwrap_or(QServoScreenGeometry {
             screen: size,
             screen_avail: size,
-            window: (size, origin),
+            window: Rect::new(origin, size),
+        });
+        EmbedderCoordinates {
+            hidpi_factor: Scale::new(1.0),
+            screen: screen_geometry.screen,
+            screen_avail: screen_geometry.screen_avail,
+            window: (screen_geometry.window.size, screen_geometry.window.origin),
             framebuffer: size,
             viewport: Rect::new(origin, size),
         }